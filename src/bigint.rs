@@ -0,0 +1,153 @@
+use std::fmt;
+
+/// A natural number of any size, as base `2^32` digits with the lowest
+/// first and no trailing zeros.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BigUint {
+    digits: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    fn normalized(mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Self { digits }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// How many bits it takes to write the number, 0 for zero.
+    pub fn bits(&self) -> u64 {
+        match self.digits.last() {
+            Some(&top) => (self.digits.len() as u64 - 1) * 32 + (32 - top.leading_zeros()) as u64,
+            None => 0,
+        }
+    }
+
+    pub fn add(&self, rhs: &Self) -> Self {
+        let len = self.digits.len().max(rhs.digits.len());
+        let mut digits = Vec::with_capacity(len + 1);
+        let mut carry = 0u64;
+        for i in 0..len {
+            let a = *self.digits.get(i).unwrap_or(&0) as u64;
+            let b = *rhs.digits.get(i).unwrap_or(&0) as u64;
+            let sum = a + b + carry;
+            digits.push(sum as u32);
+            carry = sum >> 32;
+        }
+        digits.push(carry as u32);
+        Self::normalized(digits)
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Self {
+        let mut digits = vec![0u32; self.digits.len() + rhs.digits.len()];
+        for (i, &a) in self.digits.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in rhs.digits.iter().enumerate() {
+                let t = a as u64 * b as u64 + digits[i + j] as u64 + carry;
+                digits[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            digits[i + rhs.digits.len()] = carry as u32;
+        }
+        Self::normalized(digits)
+    }
+
+    pub fn mul_small(&self, k: u64) -> Self {
+        let mut digits = Vec::with_capacity(self.digits.len() + 2);
+        let mut carry = 0u128;
+        for &d in &self.digits {
+            let t = d as u128 * k as u128 + carry;
+            digits.push(t as u32);
+            carry = t >> 32;
+        }
+        digits.extend([carry as u32, (carry >> 32) as u32]);
+        Self::normalized(digits)
+    }
+
+    /// The quotient and remainder of dividing by `k > 0`.
+    pub fn div_small(&self, k: u64) -> (Self, u64) {
+        let mut digits = vec![0u32; self.digits.len()];
+        let mut rem = 0u128;
+        for (i, &d) in self.digits.iter().enumerate().rev() {
+            let t = rem << 32 | d as u128;
+            digits[i] = (t / k as u128) as u32;
+            rem = t % k as u128;
+        }
+        (Self::normalized(digits), rem as u64)
+    }
+
+    /// The number as a `u128`, `None` if it is larger.
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.digits.len() > 4 {
+            return None;
+        }
+        Some(
+            self.digits
+                .iter()
+                .rev()
+                .fold(0u128, |acc, &d| acc << 32 | d as u128),
+        )
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(x: u64) -> Self {
+        Self::normalized(vec![x as u32, (x >> 32) as u32])
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // nine decimal digits at a time, the lowest first
+        let mut chunks = Vec::new();
+        let mut x = self.clone();
+        while !x.is_zero() {
+            let (q, r) = x.div_small(1_000_000_000);
+            chunks.push(r);
+            x = q;
+        }
+        let Some((top, rest)) = chunks.split_last() else {
+            return write!(f, "0");
+        };
+        write!(f, "{}", top)?;
+        for chunk in rest.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let max = BigUint::from(u64::MAX);
+        let square = max.mul(&max);
+        assert_eq!(
+            square.to_string(),
+            "340282366920938463426481119284349108225"
+        );
+        assert_eq!(square.to_u128(), Some(u64::MAX as u128 * u64::MAX as u128));
+        assert_eq!(square.bits(), 128);
+        assert_eq!(
+            max.add(&BigUint::from(1)).to_string(),
+            "18446744073709551616"
+        );
+        assert_eq!(max.mul_small(u64::MAX), square);
+        assert_eq!(square.div_small(u64::MAX), (max, 0));
+        assert_eq!(BigUint::from(1000).div_small(7), (BigUint::from(142), 6));
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert_eq!(BigUint::from(0), BigUint::zero());
+        assert_eq!(square.mul(&square).to_u128(), None);
+    }
+}
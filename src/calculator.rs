@@ -1,76 +1,288 @@
 use anyhow::{bail, Result};
 use lazy_static::lazy_static;
 use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::sync::Arc;
 
-use crate::errors::CaculatorError;
+use crate::calculus;
+use crate::complex::Complex;
+use crate::context::{AngleMode, Context};
+use crate::currency::{self, RateTable};
+use crate::datetime::Date;
+use crate::errors::{CaculatorError, Domain, Span};
+use crate::expr::Expr;
+use crate::format::Format;
+use crate::matrix::Matrix;
+use crate::number::Number;
+use crate::random::{self, Rng};
+use crate::solve;
+use crate::units;
+use crate::value::{from_f64, Value, LIST};
+use crate::word;
 
 // operator precendence
 lazy_static! {
-    static ref PRECEDENCE: HashMap<char, u8> = {
-        HashMap::from([
-            (')', 6),
-            ('^', 5),
-            ('*', 4),
-            ('/', 3),
-            ('+', 2),
-            ('-', 1),
-            ('(', 0),
-        ])
-    };
+    static ref PRECEDENCE: HashMap<char, u8> = HashMap::from([
+        ('^', 8),
+        (IMPLICIT_MUL, 6),
+        (IMPLICIT_ADD, 5),
+        ('*', 4),
+        ('/', 4),
+        ('\\', 4),
+        ('%', 4),
+        (ELEMENT_MUL, 4),
+        (ELEMENT_DIV, 4),
+        (PLUS_MINUS, 3),
+        ('+', 2),
+        ('-', 2),
+        (CONVERT, 1),
+        ('(', 0),
+    ]);
 }
 
-#[derive(Debug, PartialEq)]
-enum Token {
+// unary minus binds tighter than `*` but looser than `^`, so `-2^2` is `-4`
+const NEG_PRECEDENCE: u8 = 7;
+
+// juxtaposition binds tighter than `*` and `/`, so `10 m / 2 s` is `5 m/s`
+const IMPLICIT_MUL: char = '⋅';
+
+// a number right after a unit continues a compound quantity: `1h 30min`
+const IMPLICIT_ADD: char = '⊕';
+
+// an uncertainty binds looser than a product: `2 * 1.5 ± 0.1` is `3 ± 0.1`
+const PLUS_MINUS: char = '±';
+
+// entry by entry products and quotients of matrices, written `.*` and `./`
+const ELEMENT_MUL: char = '⊙';
+const ELEMENT_DIV: char = '⊘';
+
+// `sum(i, a, b, f)` and `prod(i, a, b, f)` run at most this many steps
+const MAX_ITERATIONS: usize = 1_000_000;
+
+// the unit conversion operator, written `to` as in `5 ft to m`
+const CONVERT: char = '→';
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<N> {
+    Op(char),
+    Num(N),
+    Ident(String),
+    Date(Date),
+}
+
+/// An entry of the operator stack.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operator {
     Op(char),
-    Num(f64),
+    Neg,
+    // function name and the number of arguments seen so far
+    Func(String, usize),
+}
+
+impl Operator {
+    fn precedence(&self) -> u8 {
+        match self {
+            Operator::Op(op) => *PRECEDENCE.get(op).unwrap_or(&0),
+            Operator::Neg => NEG_PRECEDENCE,
+            Operator::Func(..) => 0,
+        }
+    }
 }
 
+/// Evaluates an expression over the numeric backend `N`, complex numbers by default.
 #[derive(Debug, Clone)]
-pub struct Caculator {
+pub struct Caculator<N: Number = Complex> {
     pub exp: String,
     pub chars: Vec<char>,
-    pub result: VecDeque<f64>,
-    pub op_stack: VecDeque<char>,
+    pub result: VecDeque<Value<N>>,
+    pub op_stack: VecDeque<Operator>,
+    pub rates: Option<Arc<RateTable>>,
+    pub context: Context,
+    used_rates: bool,
+    // variables in scope, innermost last: bound to numbers by `sum(i, 1, 10, f)`
+    // or to themselves as expressions, like the `x` of `diff(x^2, x)`
+    scope: Vec<(String, Value<N>)>,
+    // the summed error of numeric integrals and derivatives
+    error_estimate: Option<f64>,
+    // draws the random numbers, seeded again by every evaluation
+    rng: Rng,
+    // where each token and each entry of `op_stack` is in the expression
+    spans: Vec<Span>,
+    op_spans: VecDeque<Span>,
 }
 
 impl Caculator {
     pub fn new(exp: String) -> Self {
+        Self::with_number(exp)
+    }
+}
+
+impl<N: Number> Caculator<N> {
+    /// Creates a calculator over a chosen backend, e.g. `Caculator::<Rational>::with_number`.
+    pub fn with_number(exp: String) -> Self {
         Self {
             exp: exp.clone(),
             chars: exp.chars().collect(),
             result: VecDeque::new(),
             op_stack: VecDeque::new(),
+            rates: None,
+            context: Context::default(),
+            used_rates: false,
+            scope: Vec::new(),
+            error_estimate: None,
+            rng: Rng::from_clock(),
+            spans: Vec::new(),
+            op_spans: VecDeque::new(),
+        }
+    }
+
+    /// Uses `rates` to convert currency amounts such as `120 USD to EUR`.
+    pub fn with_rates(mut self, rates: Arc<RateTable>) -> Self {
+        self.rates = Some(rates);
+        self
+    }
+
+    /// Evaluates under `context`, e.g. with angles in degrees.
+    pub fn with_context(mut self, context: Context) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// The date of the exchange rates, if the last evaluation used any.
+    pub fn rates_date(&self) -> Option<&str> {
+        match &self.rates {
+            Some(rates) if self.used_rates => Some(&rates.date),
+            _ => None,
         }
     }
 
+    /// An estimate of the absolute error of the last evaluation, if it
+    /// computed integrals or derivatives numerically.
+    pub fn error_estimate(&self) -> Option<f64> {
+        self.error_estimate
+    }
+
+    /// Evaluates the expression, failing if the result is not a real number.
     pub fn calculate(&mut self) -> Result<f64> {
+        let r = self.evaluate()?;
+        match &r {
+            Value::Num(n) => match n.to_f64() {
+                Some(v) => Ok(v),
+                None => bail!(CaculatorError::NotReal(r.to_string())),
+            },
+            _ => bail!(CaculatorError::NotReal(r.to_string())),
+        }
+    }
+
+    /// Evaluates the expression over the backend's numbers.
+    pub fn evaluate(&mut self) -> Result<Value<N>> {
         println!("calculate: {}", self.exp);
-        let tokens = self.parse_chars()?;
-        self.do_calculation(tokens)
+        let (tokens, spans) = self.tokenize()?;
+        self.spans = spans;
+        self.error_estimate = None;
+        self.rng = match self.context.seed {
+            Some(seed) => Rng::new(seed),
+            None => Rng::from_clock(),
+        };
+        let value = if tokens.contains(&Token::Op(';')) {
+            self.system(tokens)?
+        } else {
+            self.do_calculation(tokens)?
+        };
+        // in programmer mode even a bare literal takes the integer type
+        match (self.context.word, value) {
+            (Some(word), Value::Num(n)) => Ok(Value::Num(word.wrap(&n)?)),
+            (_, value) => Ok(value),
+        }
     }
 
-    fn parse_chars(&self) -> anyhow::Result<Vec<Token>> {
-        if self.chars.len() == 0 {
+    #[cfg(test)]
+    fn parse_chars(&self) -> anyhow::Result<Vec<Token<N>>> {
+        Ok(self.tokenize()?.0)
+    }
+
+    // splits the expression into tokens, along with the span each one came from
+    fn tokenize(&self) -> anyhow::Result<(Vec<Token<N>>, Vec<Span>)> {
+        if self.chars.is_empty() {
             bail!(CaculatorError::InvalidExpression(self.exp.clone()));
         }
 
-        let mut tokens: Vec<Token> = Vec::new();
+        let mut tokens: Vec<Token<N>> = Vec::new();
+        let mut spans: Vec<Span> = Vec::new();
         let mut idx = 0;
+        // how many `[` are open, inside which a pasted column is a list
+        let mut brackets = 0;
 
         loop {
             if idx >= self.chars.len() {
                 break;
             }
 
+            let start = idx;
             let ch = self.chars[idx];
             match ch {
+                '0'..='9' if self.parse_date(idx).is_some() => {
+                    let (date, new_idx) = self.parse_date(idx).unwrap();
+                    push_implicit_mul(&mut tokens);
+                    tokens.push(Token::Date(Date::parse(&date)?));
+                    idx = new_idx;
+                }
                 '0'..='9' => {
                     let (num, new_idx) = self.parse_num(ch, idx);
-                    tokens.push(Token::Num(num.parse()?));
+                    match tokens.last() {
+                        Some(Token::Ident(name)) if units::is_unit(name) => {
+                            tokens.push(Token::Op(IMPLICIT_ADD));
+                        }
+                        _ => push_implicit_mul(&mut tokens),
+                    }
+                    tokens.push(Token::Num(N::parse(&num)?));
+                    idx = new_idx;
+                }
+                'a'..='z' | 'A'..='Z' | '_' => {
+                    let (name, new_idx) = self.parse_ident(idx);
+                    if name == "to" {
+                        tokens.push(Token::Op(CONVERT));
+                    } else {
+                        push_implicit_mul(&mut tokens);
+                        tokens.push(Token::Ident(name));
+                    }
                     idx = new_idx;
                 }
+                '\n' | '\t' if brackets > 0 && self.ends_entry(&tokens, idx) => {
+                    tokens.push(Token::Op(','));
+                    idx += 1;
+                }
                 ' ' | '\n' | '\t' | '\r' => idx += 1,
-                '+' | '-' | '*' | '/' | '^' | '(' | ')' => {
+                '(' => {
+                    // a name right before `(` is a function call, not a product
+                    if !matches!(tokens.last(), Some(Token::Ident(_))) {
+                        push_implicit_mul(&mut tokens);
+                    }
+                    tokens.push(Token::Op(ch));
+                    idx += 1;
+                }
+                '[' => {
+                    brackets += 1;
+                    push_implicit_mul(&mut tokens);
+                    tokens.push(Token::Ident(LIST.to_string()));
+                    tokens.push(Token::Op('('));
+                    idx += 1;
+                }
+                ']' => {
+                    brackets -= 1;
+                    tokens.push(Token::Op(')'));
+                    idx += 1;
+                }
+                '.' if matches!(self.chars.get(idx + 1), Some('*') | Some('/')) => {
+                    let op = match self.chars[idx + 1] {
+                        '*' => ELEMENT_MUL,
+                        _ => ELEMENT_DIV,
+                    };
+                    tokens.push(Token::Op(op));
+                    idx += 2;
+                }
+                '+' | '-' | '*' | '/' | '\\' | '%' | '^' | ')' | ',' | '=' | ';' | CONVERT
+                | PLUS_MINUS => {
                     tokens.push(Token::Op(ch));
                     idx += 1;
                 }
@@ -78,9 +290,20 @@ impl Caculator {
                     bail!(CaculatorError::UnsupportedOperator(ch));
                 }
             }
+            // implied operators share the span of the token they precede
+            spans.resize(tokens.len(), Span { start, end: idx });
         }
 
-        Ok(tokens)
+        Ok((tokens, spans))
+    }
+
+    // a line break or tab between two entries separates them like a comma
+    fn ends_entry(&self, tokens: &[Token<N>], idx: usize) -> bool {
+        let after = self.chars[idx..].iter().find(|c| !c.is_whitespace());
+        matches!(
+            tokens.last(),
+            Some(Token::Num(_)) | Some(Token::Ident(_)) | Some(Token::Op(')'))
+        ) && !matches!(after, None | Some(']') | Some(','))
     }
 
     fn parse_num(&self, ch: char, idx: usize) -> (String, usize) {
@@ -92,10 +315,7 @@ impl Caculator {
 
         while new_idx < self.chars.len() {
             let ch = self.chars[new_idx];
-            if ch == '.' {
-                num.push(ch);
-                new_idx += 1;
-            } else if ch.is_ascii_digit() {
+            if ch == '.' || ch.is_ascii_digit() {
                 num.push(ch);
                 new_idx += 1;
             } else {
@@ -103,10 +323,74 @@ impl Caculator {
             }
         }
 
+        // an exponent, as the shortest format writes `1e21`; a bare `2e` is
+        // still `2*e`
+        let digit_at = |i: usize| self.chars.get(i).is_some_and(|c| c.is_ascii_digit());
+        if matches!(self.chars.get(new_idx), Some('e' | 'E')) {
+            let sign = matches!(self.chars.get(new_idx + 1), Some('+' | '-')) as usize;
+            if digit_at(new_idx + 1 + sign) {
+                let mut end = new_idx + 1 + sign;
+                while digit_at(end) {
+                    end += 1;
+                }
+                num.extend(&self.chars[new_idx..end]);
+                new_idx = end;
+            }
+        }
+
         (num, new_idx)
     }
 
-    fn do_calculation(&mut self, tokens: Vec<Token>) -> Result<f64> {
+    // a date literal: `2026-12-25`, optionally followed by a time as
+    // `T14:30`, ` 14:30` or ` 14:30:15`
+    fn parse_date(&self, idx: usize) -> Option<(String, usize)> {
+        let matches = |start: usize, pattern: &str| {
+            pattern.chars().enumerate().all(|(i, p)| {
+                let ch = self.chars.get(start + i);
+                match p {
+                    'd' => ch.is_some_and(|c| c.is_ascii_digit()),
+                    _ => ch == Some(&p),
+                }
+            })
+        };
+
+        let date_pattern = "dddd-dd-dd";
+        if !matches(idx, date_pattern) || matches(idx + date_pattern.len(), "d") {
+            return None;
+        }
+        let mut end = idx + date_pattern.len();
+        for sep in ["T", " "] {
+            let time = format!("{}dd:dd", sep);
+            if matches(end, &time) {
+                end += time.len();
+                if matches(end, ":dd") {
+                    end += 3;
+                }
+                break;
+            }
+        }
+
+        Some((self.chars[idx..end].iter().collect(), end))
+    }
+
+    fn parse_ident(&self, idx: usize) -> (String, usize) {
+        let mut name = String::new();
+        let mut new_idx = idx;
+
+        while new_idx < self.chars.len() {
+            let ch = self.chars[new_idx];
+            if ch.is_ascii_alphanumeric() || ch == '_' {
+                name.push(ch);
+                new_idx += 1;
+            } else {
+                break;
+            }
+        }
+
+        (name, new_idx)
+    }
+
+    fn do_calculation(&mut self, tokens: Vec<Token<N>>) -> Result<Value<N>> {
         let mut idx = 0;
         loop {
             if idx >= tokens.len() {
@@ -114,21 +398,55 @@ impl Caculator {
             }
 
             let token = tokens.get(idx).unwrap();
-            match *token {
+            match token {
                 Token::Num(num) => {
-                    self.result.push_back(num);
+                    self.result.push_back(Value::Num(num.clone()));
+                    idx += 1;
+                }
+                Token::Date(date) => {
+                    self.result.push_back(Value::Date(*date));
+                    idx += 1;
+                }
+                Token::Ident(name)
+                    if tokens.get(idx + 1) == Some(&Token::Op('('))
+                        && is_special_form(name, &tokens, idx) =>
+                {
+                    idx = self.special_form(name, &tokens, idx)?;
+                }
+                Token::Ident(name) => {
+                    if tokens.get(idx + 1) == Some(&Token::Op('(')) {
+                        // `f()` has no arguments, anything else starts with one
+                        let argc = match tokens.get(idx + 2) {
+                            Some(Token::Op(')')) => 0,
+                            _ => 1,
+                        };
+                        self.push_op(Operator::Func(name.clone(), argc), idx);
+                    } else {
+                        let value = self.lookup(name)?;
+                        self.result.push_back(value);
+                    }
                     idx += 1;
                 }
-                Token::Op(op) => match op {
+                Token::Op(op) => match *op {
                     '(' => {
-                        self.op_stack.push_back(op);
+                        self.push_op(Operator::Op(*op), idx);
                         idx += 1;
                     }
                     ')' => {
                         idx = self.pop_util_left_parenthesis(idx)?;
                     }
+                    ',' => {
+                        idx = self.pop_util_comma(idx)?;
+                    }
+                    '-' if is_unary(&tokens, idx) => {
+                        self.push_op(Operator::Neg, idx);
+                        idx += 1;
+                    }
+                    '+' if is_unary(&tokens, idx) => {
+                        idx += 1;
+                    }
                     _ => {
-                        idx = self.push_or_calc(op, idx)?;
+                        idx = self.push_or_calc(*op, idx)?;
                     }
                 },
             }
@@ -143,22 +461,363 @@ impl Caculator {
         Ok(r.unwrap())
     }
 
+    // a function taking an expression rather than a value, like `diff(f, x)`:
+    // its first argument is evaluated again with the variable kept symbolic
+    fn special_form(&mut self, name: &str, tokens: &[Token<N>], idx: usize) -> Result<usize> {
+        let (args, end) = split_args(tokens, idx + 1)?;
+        let result = match (name, args.as_slice()) {
+            // other free names are constants, so `diff(a*x^2, x)` is `2*a*x`
+            ("diff", [f, var]) => {
+                let var = self.variable(tokens, var.clone())?;
+                let mut vars = self.free_variables(tokens, f.clone())?;
+                if !vars.contains(&var) {
+                    vars.push(var.clone());
+                }
+                let f = self.symbolic(tokens, f.clone(), &vars)?;
+                f.diff(&var)?.simplify()?
+            }
+            ("simplify", [f]) => {
+                let vars = self.free_variables(tokens, f.clone())?;
+                self.symbolic(tokens, f.clone(), &vars)?.simplify()?
+            }
+            ("solve", [eq, var]) => {
+                let var = self.variable(tokens, var.clone())?;
+                let f = self.equation(tokens, eq.clone(), std::slice::from_ref(&var))?;
+                match solve::root(real_fn(&f, &var), 1.0) {
+                    Some(x) => Value::Num(from_f64(x)?),
+                    None => bail!(CaculatorError::NoRoot(self.source(eq.clone()))),
+                }
+            }
+            // every root in a range
+            ("solve", [eq, var, a, b]) => {
+                let var = self.variable(tokens, var.clone())?;
+                let f = self.equation(tokens, eq.clone(), std::slice::from_ref(&var))?;
+                let (a, b) = (self.real(tokens, a.clone())?, self.real(tokens, b.clone())?);
+                let roots = solve::roots(real_fn(&f, &var), a, b);
+                if roots.is_empty() {
+                    bail!(CaculatorError::NoRoot(self.source(eq.clone())));
+                }
+                let roots = roots.into_iter().map(from_f64).collect::<Result<_>>()?;
+                Value::Matrix(Matrix::vector(roots)?)
+            }
+            ("root", [f, var, guess]) => {
+                let var = self.variable(tokens, var.clone())?;
+                let g = self.equation(tokens, f.clone(), std::slice::from_ref(&var))?;
+                let guess = self.real(tokens, guess.clone())?;
+                match solve::root(real_fn(&g, &var), guess) {
+                    Some(x) => Value::Num(from_f64(x)?),
+                    None => bail!(CaculatorError::NoRoot(self.source(f.clone()))),
+                }
+            }
+            ("integrate", [f, var, a, b]) => {
+                let var = self.variable(tokens, var.clone())?;
+                let g = self.symbolic(tokens, f.clone(), std::slice::from_ref(&var))?;
+                let (a, b) = (self.real(tokens, a.clone())?, self.real(tokens, b.clone())?);
+                match calculus::integrate(real_fn(&g.to_expr()?, &var), a, b) {
+                    Some((y, error)) => self.estimated(y, error)?,
+                    None => bail!(CaculatorError::NoConvergence(self.source(f.clone()))),
+                }
+            }
+            ("nderiv", [f, var, at]) => {
+                let var = self.variable(tokens, var.clone())?;
+                let g = self.symbolic(tokens, f.clone(), std::slice::from_ref(&var))?;
+                let at = self.real(tokens, at.clone())?;
+                match calculus::derivative(real_fn(&g.to_expr()?, &var), at) {
+                    Some((y, error)) => self.estimated(y, error)?,
+                    None => bail!(CaculatorError::NoConvergence(self.source(f.clone()))),
+                }
+            }
+            ("sum" | "prod", [var, a, b, f]) => {
+                let var = self.variable(tokens, var.clone())?;
+                let (a, b) = (
+                    self.bound(tokens, a.clone())?,
+                    self.bound(tokens, b.clone())?,
+                );
+                self.iterate(name == "prod", tokens, f.clone(), &var, a, b)?
+            }
+            _ => bail!(CaculatorError::ArgumentCount(name.to_string(), arity(name))),
+        };
+        self.result.push_back(result);
+        Ok(end)
+    }
+
+    // evaluates the tokens in `range` with `vars` as free variables
+    fn symbolic(
+        &mut self,
+        tokens: &[Token<N>],
+        range: Range<usize>,
+        vars: &[String],
+    ) -> Result<Value<N>> {
+        let bindings = vars
+            .iter()
+            .map(|v| (v.clone(), Value::Expr(Expr::Var(v.clone()))))
+            .collect();
+        self.scoped(tokens, range, bindings)
+    }
+
+    // evaluates the tokens in `range` with more variables in scope
+    fn scoped(
+        &mut self,
+        tokens: &[Token<N>],
+        range: Range<usize>,
+        bindings: Vec<(String, Value<N>)>,
+    ) -> Result<Value<N>> {
+        let mut caculator = Self {
+            result: VecDeque::new(),
+            op_stack: VecDeque::new(),
+            op_spans: VecDeque::new(),
+            spans: self.spans[range.clone()].to_vec(),
+            scope: [self.scope.clone(), bindings].concat(),
+            ..self.clone()
+        };
+        let value = caculator.do_calculation(tokens[range].to_vec())?;
+        self.used_rates |= caculator.used_rates;
+        // later draws continue where the inner evaluation stopped
+        self.rng = caculator.rng;
+        if let Some(error) = caculator.error_estimate {
+            self.estimated(0.0, error)?;
+        }
+        Ok(value)
+    }
+
+    // a numeric result, adding its error to the evaluation's
+    fn estimated(&mut self, y: f64, error: f64) -> Result<Value<N>> {
+        self.error_estimate = Some(self.error_estimate.unwrap_or(0.0) + error);
+        Ok(Value::Num(from_f64(y)?))
+    }
+
+    // `lhs = rhs` as the expression `lhs - rhs` in `vars`, whose roots solve it
+    fn equation(
+        &mut self,
+        tokens: &[Token<N>],
+        range: Range<usize>,
+        vars: &[String],
+    ) -> Result<Expr<N>> {
+        let mut depth = 0;
+        let mut eq = None;
+        for i in range.clone() {
+            match tokens[i] {
+                Token::Op('(') => depth += 1,
+                Token::Op(')') => depth -= 1,
+                Token::Op('=') if depth == 0 && eq.is_none() => eq = Some(i),
+                Token::Op('=') if depth == 0 => {
+                    bail!(CaculatorError::InvalidExpression(self.source(range)))
+                }
+                _ => {}
+            }
+        }
+        let f = match eq {
+            None => self.symbolic(tokens, range, vars)?,
+            Some(i) => {
+                let lhs = self.symbolic(tokens, range.start..i, vars)?;
+                lhs.sub(&self.symbolic(tokens, i + 1..range.end, vars)?)?
+            }
+        };
+        f.to_expr()
+    }
+
+    // linear equations separated by `;`, like `2x + y = 5; x - y = 1`, solved
+    // for their unknowns in order of first appearance
+    fn system(&mut self, tokens: Vec<Token<N>>) -> Result<Value<N>> {
+        let mut equations = Vec::new();
+        let (mut depth, mut start) = (0, 0);
+        for (i, token) in tokens.iter().enumerate() {
+            match token {
+                Token::Op('(') => depth += 1,
+                Token::Op(')') => depth -= 1,
+                Token::Op(';') if depth == 0 => {
+                    equations.push(start..i);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        // a trailing `;` ends the last equation
+        if start < tokens.len() {
+            equations.push(start..tokens.len());
+        }
+        if equations.iter().any(|eq| eq.is_empty()) {
+            bail!(CaculatorError::InvalidExpression(self.exp.clone()));
+        }
+        let vars = self.free_variables(&tokens, 0..tokens.len())?;
+        if vars.is_empty() {
+            bail!(CaculatorError::InvalidExpression(self.exp.clone()));
+        }
+
+        // each equation is a row of coefficients and a right hand side
+        let zero = Value::Num(N::parse("0")?);
+        let (mut coeffs, mut rhs) = (Vec::new(), Vec::new());
+        for eq in equations {
+            let f = self.equation(&tokens, eq.clone(), &vars)?;
+            for var in &vars {
+                match Value::from_expr(f.diff(var)?.simplify()?) {
+                    Value::Num(a) => coeffs.push(a),
+                    _ => bail!(CaculatorError::NotLinear(self.source(eq))),
+                }
+            }
+            let mut c = Value::from_expr(f);
+            for var in &vars {
+                c = c.to_expr()?.eval(var, &zero)?;
+            }
+            rhs.push(c.to_number()?.neg()?);
+        }
+        let a = Matrix::new(rhs.len(), vars.len(), coeffs)?;
+        Ok(Value::Matrix(a.solve_system(&Matrix::vector(rhs)?)?))
+    }
+
+    // an argument that must be a real number, like the guess of `root(f, x, guess)`
+    fn real(&mut self, tokens: &[Token<N>], range: Range<usize>) -> Result<f64> {
+        let v = self.symbolic(tokens, range, &[])?;
+        match v.to_number()?.to_f64() {
+            Some(x) => Ok(x),
+            None => bail!(CaculatorError::NotReal(v.to_string())),
+        }
+    }
+
+    // the sum or product of `f` over `var` from `a` to `b`, in the backend's
+    // arithmetic so exact numbers stay exact; empty ranges give 0 and 1
+    fn iterate(
+        &mut self,
+        product: bool,
+        tokens: &[Token<N>],
+        f: Range<usize>,
+        var: &str,
+        a: i64,
+        b: i64,
+    ) -> Result<Value<N>> {
+        let count = (b as i128 - a as i128 + 1).max(0);
+        if count > MAX_ITERATIONS as i128 {
+            bail!(CaculatorError::IterationLimit(MAX_ITERATIONS));
+        }
+        let mut acc = Value::Num(N::parse(if product { "1" } else { "0" })?);
+        for k in a..=b {
+            let n = N::parse(&k.unsigned_abs().to_string())?;
+            let k = if k < 0 { n.neg()? } else { n };
+            let y = self.scoped(tokens, f.clone(), vec![(var.to_string(), Value::Num(k))])?;
+            acc = if product { acc.mul(&y)? } else { acc.add(&y)? };
+        }
+        Ok(acc)
+    }
+
+    // an integer bound of `sum(i, a, b, f)`
+    fn bound(&mut self, tokens: &[Token<N>], range: Range<usize>) -> Result<i64> {
+        let x = self.real(tokens, range.clone())?;
+        if x.fract() != 0.0 || x.abs() > i64::MAX as f64 {
+            bail!(CaculatorError::InvalidExpression(self.source(range)));
+        }
+        Ok(x as i64)
+    }
+
+    // the names in `range` that mean nothing else, the `x` and `y` of `simplify(x*y/x)`;
+    // a unit or currency name is a variable too once it appears other than
+    // after a number, so `t + t` is `2*t` while `x + 1 USD` keeps its unit
+    fn free_variables(&mut self, tokens: &[Token<N>], range: Range<usize>) -> Result<Vec<String>> {
+        let names = || {
+            tokens
+                .iter()
+                .enumerate()
+                .take(range.end)
+                .skip(range.start)
+                .filter_map(|(i, token)| match token {
+                    Token::Ident(name) if tokens.get(i + 1) != Some(&Token::Op('(')) => {
+                        Some((i, name))
+                    }
+                    _ => None,
+                })
+        };
+        let bare: Vec<&String> = names()
+            .filter(|&(i, _)| {
+                i < range.start + 2
+                    || tokens[i - 1] != Token::Op(IMPLICIT_MUL)
+                    || !matches!(tokens[i - 2], Token::Num(_))
+            })
+            .map(|(_, name)| name)
+            .collect();
+        let mut vars: Vec<String> = Vec::new();
+        for (_, name) in names() {
+            if vars.contains(name) {
+                continue;
+            }
+            if self.variable_value(name).is_none()
+                && bare.contains(&name)
+                && (units::is_unit(name) || currency::is_currency_code(name))
+            {
+                vars.push(name.clone());
+                continue;
+            }
+            if let Err(e) = self.lookup(name) {
+                match e.downcast_ref::<CaculatorError>() {
+                    Some(CaculatorError::UnknownIdentifier(_)) => vars.push(name.clone()),
+                    _ => return Err(e),
+                }
+            }
+        }
+        Ok(vars)
+    }
+
+    // the name of a bound variable, the `x` of `diff(f, x)`
+    fn variable(&self, tokens: &[Token<N>], range: Range<usize>) -> Result<String> {
+        match &tokens[range.clone()] {
+            [Token::Ident(name)] => Ok(name.clone()),
+            _ => bail!(CaculatorError::InvalidExpression(self.source(range))),
+        }
+    }
+
+    // the part of the expression the tokens in `range` came from
+    fn source(&self, range: Range<usize>) -> String {
+        match (
+            self.spans.get(range.start),
+            self.spans.get(range.end.wrapping_sub(1)),
+        ) {
+            (Some(first), Some(last)) if !range.is_empty() => {
+                self.chars[first.start..last.end].iter().collect()
+            }
+            _ => String::new(),
+        }
+    }
+
     fn pop_util_left_parenthesis(&mut self, idx: usize) -> anyhow::Result<usize> {
         loop {
-            if self.op_stack.len() < 1 {
+            if self.op_stack.is_empty() {
                 bail!(CaculatorError::UnBalancedParenthesis);
             }
 
-            let op = self.op_stack.pop_back();
+            let op = self.pop_op();
             if op.is_none() {
                 bail!(CaculatorError::InvalidExpression(self.exp.clone()));
             }
-            let operator = op.unwrap();
-            if operator == '(' {
+            let (operator, span) = op.unwrap();
+            if operator == Operator::Op('(') {
+                break;
+            }
+
+            self.apply(operator, span)?;
+        }
+
+        // the parenthesis closes a function call
+        if let Some(Operator::Func(..)) = self.op_stack.back() {
+            let (func, span) = self.pop_op().unwrap();
+            self.apply(func, span)?;
+        }
+
+        Ok(idx + 1)
+    }
+
+    // a comma finishes one function argument: calculate everything back to the
+    // call's left parenthesis and count the next argument
+    fn pop_util_comma(&mut self, idx: usize) -> anyhow::Result<usize> {
+        while let Some(operator) = self.op_stack.back() {
+            if *operator == Operator::Op('(') {
                 break;
             }
+            let (operator, span) = self.pop_op().unwrap();
+            self.apply(operator, span)?;
+        }
 
-            self.calc_with_op(operator)?;
+        let len = self.op_stack.len();
+        match self.op_stack.get_mut(len.wrapping_sub(2)) {
+            Some(Operator::Func(_, argc)) => *argc += 1,
+            _ => bail!(CaculatorError::InvalidExpression(self.exp.clone())),
         }
 
         Ok(idx + 1)
@@ -166,43 +825,141 @@ impl Caculator {
 
     // push or do calculation
     // 1. if there are no operators in the operator stack, push the new operator into the operator stack,
-    // 2. else if current operator has higher precendence than the new operator: op,
-    // then do calculation with current op in the stack, and pop the current op.
+    // 2. else while the operator on top of the stack has higher (or equal, for left-associative
+    // operators) precendence than the new operator: op,
+    // do calculation with the top op in the stack, and pop it.
     // 3. then push the new operator into the operator stack
     fn push_or_calc(&mut self, op: char, idx: usize) -> anyhow::Result<usize> {
-        if self.op_stack.len() < 1 {
-            self.op_stack.push_back(op);
-            return Ok(idx + 1);
-        }
+        let new_prec = *PRECEDENCE
+            .get(&op)
+            .ok_or(CaculatorError::UnsupportedOperator(op))?;
+        // `^` is right associative: 2^3^2 is 2^(3^2)
+        let right_assoc = op == '^';
+
+        while let Some(current_op) = self.op_stack.back() {
+            let current_prec = current_op.precedence();
+            if current_prec < new_prec || (right_assoc && current_prec == new_prec) {
+                break;
+            }
+            if *current_op == Operator::Op('(') {
+                break;
+            }
 
-        let current_op = self.op_stack.back().unwrap();
-        let current_prec = PRECEDENCE.get(current_op).unwrap();
-        let new_prec = PRECEDENCE.get(&op).unwrap();
-        if current_prec >= new_prec {
-            self.calc_with_op(*current_op)?;
-            self.op_stack.pop_back();
+            let (current_op, span) = self.pop_op().unwrap();
+            self.apply(current_op, span)?;
         }
 
-        self.op_stack.push_back(op);
+        self.push_op(Operator::Op(op), idx);
         Ok(idx + 1)
     }
 
     fn pop_all_operators(&mut self) -> anyhow::Result<()> {
-        if self.op_stack.len() < 1 {
-            return Ok(());
+        while let Some((op, span)) = self.pop_op() {
+            if op == Operator::Op('(') {
+                bail!(CaculatorError::UnBalancedParenthesis);
+            }
+            self.apply(op, span)?;
         }
+        Ok(())
+    }
 
-        loop {
-            if self.op_stack.len() < 1 {
-                break;
+    // pushes an operator along with the span of the token at `idx`
+    fn push_op(&mut self, operator: Operator, idx: usize) {
+        self.op_stack.push_back(operator);
+        self.op_spans
+            .push_back(self.spans.get(idx).copied().unwrap_or_default());
+    }
+
+    fn pop_op(&mut self) -> Option<(Operator, Span)> {
+        let operator = self.op_stack.pop_back()?;
+        Some((operator, self.op_spans.pop_back().unwrap_or_default()))
+    }
+
+    fn apply(&mut self, operator: Operator, span: Span) -> anyhow::Result<()> {
+        // describe the operation up front, its operands are consumed by it
+        let strict = self.context.strict.then(|| self.describe(&operator));
+        let is_log = matches!(&operator, Operator::Func(name, _) if name == "ln" || name == "log");
+
+        let applied = match operator {
+            Operator::Op(op) => self.calc_with_op(op),
+            Operator::Neg => match (self.result.pop_back(), self.context.word) {
+                (Some(Value::Num(n)), Some(word)) => {
+                    word.neg(&n).map(|n| self.result.push_back(Value::Num(n)))
+                }
+                (Some(num), _) => num.neg().map(|v| self.result.push_back(v)),
+                (None, _) => Err(CaculatorError::MissingOperand('-', 1).into()),
+            },
+            Operator::Func(name, argc) => self.calc_with_func(&name, argc),
+        };
+        // overflow in exact arithmetic and integers outside a function's
+        // domain are reported where they happened
+        applied.map_err(|e| match e.downcast::<CaculatorError>() {
+            Ok(CaculatorError::Overflow { op, span: None }) => CaculatorError::Overflow {
+                op,
+                span: Some(span),
             }
+            .into(),
+            Ok(CaculatorError::DomainError {
+                kind,
+                op,
+                span: None,
+            }) => CaculatorError::DomainError {
+                kind,
+                op,
+                span: Some(span),
+            }
+            .into(),
+            Ok(e) => e.into(),
+            Err(e) => e,
+        })?;
 
-            let op = self.op_stack.pop_back().unwrap();
-            self.calc_with_op(op)?;
+        let (Some(op), Some(result)) = (strict, self.result.back()) else {
+            return Ok(());
+        };
+        if result.is_nan() || (is_log && !result.is_finite()) {
+            bail!(CaculatorError::DomainError {
+                kind: Domain::NotANumber,
+                op,
+                span: Some(span)
+            });
+        }
+        if !result.is_finite() {
+            bail!(CaculatorError::Overflow {
+                op,
+                span: Some(span)
+            });
         }
         Ok(())
     }
 
+    // the operator applied to the operands on top of the result stack, e.g. `10 ^ 400`
+    fn describe(&self, operator: &Operator) -> String {
+        let operands = |n: usize| -> Vec<String> {
+            let skip = self.result.len().saturating_sub(n);
+            let format = Format::default();
+            self.result
+                .iter()
+                .skip(skip)
+                .map(|v| v.format(&format))
+                .collect()
+        };
+        match operator {
+            Operator::Op(op) => {
+                let symbol = match *op {
+                    IMPLICIT_MUL => "*".to_string(),
+                    IMPLICIT_ADD => "+".to_string(),
+                    CONVERT => "to".to_string(),
+                    ELEMENT_MUL => ".*".to_string(),
+                    ELEMENT_DIV => "./".to_string(),
+                    op => op.to_string(),
+                };
+                operands(2).join(&format!(" {} ", symbol))
+            }
+            Operator::Neg => format!("-{}", operands(1).join("")),
+            Operator::Func(name, argc) => format!("{}({})", name, operands(*argc).join(", ")),
+        }
+    }
+
     fn calc_with_op(&mut self, operator: char) -> anyhow::Result<()> {
         if self.result.len() < 2 {
             bail!(CaculatorError::MissingOperand(operator, 2));
@@ -213,17 +970,32 @@ impl Caculator {
 
         println!("pop {} and {} from result stack", num1, num2);
 
-        let result = match operator {
-            '+' => num1 + num2,
-            '-' => num1 - num2,
-            '*' => num1 * num2,
-            '/' => {
-                if num2 == 0.0 {
-                    bail!(CaculatorError::DivideByZero);
-                }
-                num1 / num2
+        // programmer mode does integer arithmetic of the chosen width
+        if let (Some(word), Value::Num(a), Value::Num(b)) = (self.context.word, &num1, &num2) {
+            let op = if operator == IMPLICIT_MUL {
+                '*'
+            } else {
+                operator
+            };
+            if let Some(n) = word.apply(op, a, b)? {
+                self.result.push_back(Value::Num(n));
+                return Ok(());
             }
-            '^' => num1.powf(num2),
+        }
+
+        let result = match operator {
+            '+' => num1.add(&num2)?,
+            '-' => num1.sub(&num2)?,
+            '*' | IMPLICIT_MUL => num1.mul(&num2)?,
+            IMPLICIT_ADD => num1.add(&num2)?.without_unit(),
+            '/' => num1.div(&num2)?,
+            '\\' => num1.left_div(&num2)?,
+            '%' => num1.rem(&num2)?,
+            ELEMENT_MUL => num1.mul_elements(&num2)?,
+            ELEMENT_DIV => num1.div_elements(&num2)?,
+            PLUS_MINUS => num1.plus_minus(&num2)?,
+            '^' => num1.pow(&num2)?,
+            CONVERT => num1.convert(&num2)?,
             _ => bail!(CaculatorError::UnsupportedOperator(operator)),
         };
 
@@ -232,42 +1004,286 @@ impl Caculator {
         self.result.push_back(result);
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::vec;
+    // the innermost variable called `name` in scope
+    fn variable_value(&self, name: &str) -> Option<Value<N>> {
+        let (_, value) = self.scope.iter().rev().find(|(var, _)| var == name)?;
+        Some(value.clone())
+    }
 
-    use super::*;
+    // units take precedence over the backend's constants, currency codes
+    // are only known through the rate table
+    fn lookup(&mut self, name: &str) -> Result<Value<N>> {
+        if let Some(x) = self.variable_value(name) {
+            return Ok(x);
+        }
+        if let Some(q) = units::unit(name)? {
+            return Ok(Value::Quantity(q));
+        }
+        match name {
+            "now" => return Ok(Value::Date(Date::now())),
+            "today" => return Ok(Value::Date(Date::today())),
+            _ => {}
+        }
+        // an angle suffix scales to the angle mode: `30deg` is `pi/6` in radians
+        if let Some(unit) = AngleMode::from_suffix(name) {
+            return Ok(Value::Num(self.context.angle.factor_from(unit)?));
+        }
+        if currency::is_currency_code(name) {
+            let Some(rates) = &self.rates else {
+                bail!(CaculatorError::MissingRate(name.to_string()));
+            };
+            self.used_rates = true;
+            return Ok(Value::Quantity(rates.currency(name)?));
+        }
+        Ok(Value::Num(N::constant(name)?))
+    }
 
-    #[test]
-    fn test_parse_tokens() -> anyhow::Result<()> {
-        let exp = "1+2*3";
-        let caculator = Caculator::new(exp.to_string());
-        let tokens = caculator.parse_chars()?;
+    fn calc_with_func(&mut self, name: &str, argc: usize) -> anyhow::Result<()> {
+        if self.result.len() < argc {
+            bail!(CaculatorError::ArgumentCount(name.to_string(), argc));
+        }
 
-        assert_eq!(tokens.len(), 5);
-        assert_eq!(tokens[0], Token::Num(1 as f64));
-        assert_eq!(tokens[1], Token::Op('+'));
-        assert_eq!(tokens[2], Token::Num(2 as f64));
-        assert_eq!(tokens[3], Token::Op('*'));
-        assert_eq!(tokens[4], Token::Num(3 as f64));
+        let mut args = self.result.split_off(self.result.len() - argc);
+        let args = args.make_contiguous();
 
-        Ok(())
-    }
+        // `x(x + 1)` is a product when `x` is a variable
+        if let Some(x) = self.variable_value(name) {
+            let [arg] = args else {
+                bail!(CaculatorError::UnknownFunction(name.to_string()));
+            };
+            let product = x.mul(arg)?;
+            self.result.push_back(product);
+            return Ok(());
+        }
 
-    #[test]
-    fn test_parse_tokens1() -> anyhow::Result<()> {
-        let exp = "1.099+2.5*3.89";
-        let caculator = Caculator::new(exp.to_string());
-        let tokens = caculator.parse_chars()?;
+        // `deg2rad(x)` and friends convert between fixed angle units
+        let conversion = name.split_once('2').and_then(|(from, to)| {
+            Some((AngleMode::from_suffix(from)?, AngleMode::from_suffix(to)?))
+        });
+        if let Some((from, to)) = conversion {
+            let [x] = args else {
+                bail!(CaculatorError::ArgumentCount(name.to_string(), 1));
+            };
+            let result = x.mul(&Value::Num(to.factor_from(from)?))?;
+            self.result.push_back(result);
+            return Ok(());
+        }
 
-        assert_eq!(tokens.len(), 5);
-        assert_eq!(tokens[0], Token::Num(1.099 as f64));
-        assert_eq!(tokens[1], Token::Op('+'));
-        assert_eq!(tokens[2], Token::Num(2.5 as f64));
-        assert_eq!(tokens[3], Token::Op('*'));
-        assert_eq!(tokens[4], Token::Num(3.89 as f64));
+        if random::is_random(name) {
+            let result = self.random(name, args)?;
+            self.result.push_back(result);
+            return Ok(());
+        }
+
+        // bit patterns are those of the programmer mode type, `i64` otherwise
+        if word::is_bitwise(name) {
+            let nums = args
+                .iter()
+                .map(|v| v.to_number().cloned())
+                .collect::<Result<Vec<N>>>()?;
+            let word = self.context.word.unwrap_or_default();
+            self.result.push_back(Value::Num(word.call(name, &nums)?));
+            return Ok(());
+        }
+
+        // angles going into and out of the backend are in radians
+        let angle = self.context.angle;
+        let angle_arg = match name {
+            "sin" | "cos" | "tan" => args.first_mut(),
+            "polar" => args.get_mut(1),
+            _ => None,
+        };
+        if let Some(arg) = angle_arg {
+            *arg = arg.mul(&Value::Num(AngleMode::Radians.factor_from(angle)?))?;
+        }
+
+        let mut result = Value::call(name, args)?;
+        if matches!(name, "asin" | "acos" | "atan" | "arg") {
+            result = result.mul(&Value::Num(angle.factor_from(AngleMode::Radians)?))?;
+        }
+        self.result.push_back(result);
+        Ok(())
+    }
+
+    // draws a random number, or picks one of a list or the arguments
+    fn random(&mut self, name: &str, args: &[Value<N>]) -> Result<Value<N>> {
+        let real = |v: &Value<N>| {
+            let n = v.to_number()?;
+            match n.to_f64() {
+                Some(x) => Ok(x),
+                None => bail!(CaculatorError::NotReal(n.to_string())),
+            }
+        };
+        let x = match (name, args) {
+            ("rand", []) => self.rng.uniform(),
+            ("randn", []) => self.rng.normal(0.0, 1.0),
+            ("randn", [mu, sigma]) => {
+                let (mu, sigma) = (real(mu)?, real(sigma)?);
+                // a spread cannot be negative
+                if sigma < 0.0 || sigma.is_nan() {
+                    bail!(CaculatorError::InvalidExpression(format!(
+                        "randn({}, {})",
+                        mu, sigma
+                    )));
+                }
+                self.rng.normal(mu, sigma)
+            }
+            ("randint", [a, b]) => {
+                let (a, b) = (real(a)?, real(b)?);
+                let bounds = [a, b]
+                    .iter()
+                    .all(|x| x.fract() == 0.0 && x.abs() < 2f64.powi(63));
+                if !bounds || a > b {
+                    bail!(CaculatorError::InvalidExpression(format!(
+                        "randint({}, {})",
+                        a, b
+                    )));
+                }
+                self.rng.int(a as i64, b as i64) as f64
+            }
+            ("choice", [Value::Matrix(m)]) if m.vector => {
+                let i = self.rng.below(m.data.len() as u64) as usize;
+                return Ok(Value::Num(m.data[i].clone()));
+            }
+            ("choice", [_, ..]) => {
+                let i = self.rng.below(args.len() as u64) as usize;
+                return Ok(args[i].clone());
+            }
+            _ => {
+                let count = match name {
+                    "rand" => 0,
+                    "choice" => 1,
+                    _ => 2,
+                };
+                bail!(CaculatorError::ArgumentCount(name.to_string(), count));
+            }
+        };
+        Ok(Value::Num(from_f64(x)?))
+    }
+}
+
+// `sum` also adds up a list, it iterates only as `sum(i, a, b, f)`
+fn is_special_form<N>(name: &str, tokens: &[Token<N>], idx: usize) -> bool {
+    match name {
+        "sum" => split_args(tokens, idx + 1).is_ok_and(|(args, _)| {
+            args.len() == 4 && matches!(&tokens[args[0].clone()], [Token::Ident(_)])
+        }),
+        _ => arity(name) > 0,
+    }
+}
+
+// the number of arguments a special form takes, at least
+fn arity(name: &str) -> usize {
+    match name {
+        "simplify" => 1,
+        "diff" | "solve" => 2,
+        "root" | "nderiv" => 3,
+        "integrate" | "sum" | "prod" => 4,
+        _ => 0,
+    }
+}
+
+// `f` as a real function of `var`, `NaN` where it has no real value
+fn real_fn<'a, N: Number>(f: &'a Expr<N>, var: &'a str) -> impl Fn(f64) -> f64 + 'a {
+    move |x| {
+        let y = from_f64(x).and_then(|x| f.eval(var, &Value::Num(x)));
+        match y {
+            Ok(Value::Num(y)) => y.to_f64().unwrap_or(f64::NAN),
+            _ => f64::NAN,
+        }
+    }
+}
+
+// the token ranges of the arguments of the call whose `(` is at `open`,
+// along with the index after its `)`
+fn split_args<N>(tokens: &[Token<N>], open: usize) -> Result<(Vec<Range<usize>>, usize)> {
+    let mut args = Vec::new();
+    let mut start = open + 1;
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Op('(') => depth += 1,
+            Token::Op(',') if depth == 1 => {
+                args.push(start..i);
+                start = i + 1;
+            }
+            Token::Op(')') => {
+                depth -= 1;
+                if depth == 0 {
+                    if start < i || !args.is_empty() {
+                        args.push(start..i);
+                    }
+                    return Ok((args, i + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+    bail!(CaculatorError::UnBalancedParenthesis)
+}
+
+// insert the multiplication implied by juxtaposition (`2i`, `3(1+i)`, `(1+i)(1-i)`)
+// between a finished operand and the start of a new one
+fn push_implicit_mul<N>(tokens: &mut Vec<Token<N>>) {
+    match tokens.last() {
+        Some(Token::Num(_))
+        | Some(Token::Ident(_))
+        | Some(Token::Date(_))
+        | Some(Token::Op(')')) => {
+            tokens.push(Token::Op(IMPLICIT_MUL));
+        }
+        _ => {}
+    }
+}
+
+// a sign is unary at the start of the expression, an argument or a group,
+// or right after another operator
+fn is_unary<N>(tokens: &[Token<N>], idx: usize) -> bool {
+    if idx == 0 {
+        return true;
+    }
+    matches!(tokens[idx - 1], Token::Op(op) if op != ')')
+}
+
+#[cfg(test)]
+#[allow(clippy::unnecessary_cast)]
+mod tests {
+    use std::vec;
+
+    use super::*;
+    use crate::format::Notation;
+    use crate::rational::Rational;
+    use crate::word::{IntType, OverflowMode, WordMode};
+
+    #[test]
+    fn test_parse_tokens() -> anyhow::Result<()> {
+        let exp = "1+2*3";
+        let caculator = Caculator::<f64>::with_number(exp.to_string());
+        let tokens = caculator.parse_chars()?;
+
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[0], Token::Num(1 as f64));
+        assert_eq!(tokens[1], Token::Op('+'));
+        assert_eq!(tokens[2], Token::Num(2 as f64));
+        assert_eq!(tokens[3], Token::Op('*'));
+        assert_eq!(tokens[4], Token::Num(3 as f64));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_tokens1() -> anyhow::Result<()> {
+        let exp = "1.099+2.5*3.89";
+        let caculator = Caculator::<f64>::with_number(exp.to_string());
+        let tokens = caculator.parse_chars()?;
+
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[0], Token::Num(1.099 as f64));
+        assert_eq!(tokens[1], Token::Op('+'));
+        assert_eq!(tokens[2], Token::Num(2.5 as f64));
+        assert_eq!(tokens[3], Token::Op('*'));
+        assert_eq!(tokens[4], Token::Num(3.89 as f64));
 
         Ok(())
     }
@@ -275,7 +1291,7 @@ mod tests {
     #[test]
     fn test_parse_tokens2() -> anyhow::Result<()> {
         let exp = "1.099 + 2.5 * (3.89 - 1)";
-        let caculator = Caculator::new(exp.to_string());
+        let caculator = Caculator::<f64>::with_number(exp.to_string());
         let tokens = caculator.parse_chars()?;
 
         assert_eq!(tokens.len(), 9);
@@ -295,7 +1311,7 @@ mod tests {
     #[test]
     fn test_calc_with_op() -> anyhow::Result<()> {
         let exp = "";
-        let mut caculator = Caculator::new(exp.to_string());
+        let mut caculator = Caculator::<f64>::with_number(exp.to_string());
 
         let t_cases = vec![
             ('*', 40.96 as f64, 100 as f64, Some(4096 as f64)),
@@ -306,13 +1322,13 @@ mod tests {
         ];
 
         for case in t_cases {
-            caculator.result.push_back(case.1);
-            caculator.result.push_back(case.2);
+            caculator.result.push_back(Value::Num(case.1));
+            caculator.result.push_back(Value::Num(case.2));
 
             caculator.calc_with_op(case.0)?;
             let r = caculator.result.pop_back();
             println!("{:?}, {:?}", case, r);
-            assert_eq!(r, case.3);
+            assert_eq!(r, case.3.map(Value::Num));
         }
 
         Ok(())
@@ -321,31 +1337,34 @@ mod tests {
     #[test]
     fn test_push_or_calc() -> anyhow::Result<()> {
         let exp = "1 + 8 - 6";
-        let mut caculator = Caculator::new(exp.to_string());
+        let mut caculator = Caculator::<f64>::with_number(exp.to_string());
 
         caculator.op_stack = VecDeque::from(vec![]);
-        caculator.result = VecDeque::from(vec![1 as f64]);
+        caculator.result = VecDeque::from(vec![Value::Num(1 as f64)]);
         let t_cases = vec![
             (
                 '+',
                 8 as f64,                                 // operand to push
-                VecDeque::from(vec!['+']),                // expect op stack
+                VecDeque::from(vec![Operator::Op('+')]),  // expect op stack
                 VecDeque::from(vec![1 as f64, 8 as f64]), // expect result
             ),
             (
                 '-',
                 6 as f64,
-                VecDeque::from(vec!['-']),           // expect op stack
-                VecDeque::from(vec![9.0, 6 as f64]), // result
+                VecDeque::from(vec![Operator::Op('-')]), // expect op stack
+                VecDeque::from(vec![9.0, 6 as f64]),     // result
             ),
         ];
 
         for tc in t_cases {
             caculator.push_or_calc(tc.0, 0)?;
-            caculator.result.push_back(tc.1);
+            caculator.result.push_back(Value::Num(tc.1));
 
             assert_eq!(caculator.op_stack, tc.2);
-            assert_eq!(caculator.result, tc.3);
+            assert_eq!(
+                caculator.result,
+                tc.3.into_iter().map(Value::Num).collect::<VecDeque<_>>()
+            );
         }
 
         Ok(())
@@ -387,4 +1406,1235 @@ mod tests {
         assert_eq!(r, 1.2499999999999998);
         Ok(())
     }
+
+    #[test]
+    fn test_parse_idents() -> anyhow::Result<()> {
+        let exp = "2i + sqrt(x, 1)";
+        let caculator = Caculator::<f64>::with_number(exp.to_string());
+        let tokens = caculator.parse_chars()?;
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Num(2 as f64),
+                Token::Op(IMPLICIT_MUL),
+                Token::Ident("i".to_string()),
+                Token::Op('+'),
+                Token::Ident("sqrt".to_string()),
+                Token::Op('('),
+                Token::Ident("x".to_string()),
+                Token::Op(','),
+                Token::Num(1 as f64),
+                Token::Op(')'),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_precedence() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("1 - 2 + 3", 2.0),
+            ("8 / 2 * 4", 16.0),
+            ("2^3^2", 512.0),
+            ("-2^2", -4.0),
+            ("2 * -3", -6.0),
+            ("-(1 + 2) * 3", -9.0),
+            ("2^-1", 0.5),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::new(tc.0.to_string());
+            let r = caculator.calculate()?;
+            assert_eq!(r, tc.1, "exp: {} is not equal to {}", tc.0, tc.1);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_complex() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("sqrt(-1)", Complex::I),
+            ("(1+2i)*(3-i)", Complex::new(5.0, 5.0)),
+            ("e^(i*pi)", Complex::new(-1.0, 0.0)),
+            ("conj(3 + 4i)", Complex::new(3.0, -4.0)),
+            ("abs(3 + 4i)", Complex::from(5.0)),
+            ("re(2i + 7) + im(2i + 7)", Complex::from(9.0)),
+            ("polar(2, pi/2) * 2", Complex::new(0.0, 4.0)),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::new(tc.0.to_string());
+            let r = *caculator.evaluate()?.to_number()?;
+            assert!(
+                (r - tc.1).abs() < 1e-12,
+                "exp: {} = {} is not equal to {}",
+                tc.0,
+                r,
+                tc.1
+            );
+        }
+
+        let mut caculator = Caculator::new("(-8)^(1/3)".to_string());
+        let r = *caculator.evaluate()?.to_number()?;
+        assert!((r - Complex::new(1.0, 3f64.sqrt())).abs() < 1e-12);
+
+        let mut caculator = Caculator::new("sqrt(-4)".to_string());
+        assert!(caculator.calculate().is_err());
+
+        // results too large for a float stay visible
+        let t_cases = vec![
+            ("10^400", "inf"),
+            ("-10^400", "-inf"),
+            ("exp(1000)", "inf"),
+            ("ln(0)", "-inf"),
+            ("10^400*i", "NaN+infi"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::new(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_calcuate_errors() {
+        let t_cases = vec!["(1 + 2", "1 + 2)", "foo + 1", "bar(2)", "polar(1)", "1 / 0"];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::new(tc.to_string());
+            assert!(caculator.evaluate().is_err(), "exp: {} should fail", tc);
+        }
+    }
+
+    #[test]
+    fn test_number_backends() -> anyhow::Result<()> {
+        let exp = "(99 + 1) * (1 - 0.9) / 2^3";
+        let mut caculator = Caculator::<Rational>::with_number(exp.to_string());
+        assert_eq!(caculator.evaluate()?, Value::Num(Rational::new(5, 4)?));
+
+        let mut caculator = Caculator::<Rational>::with_number("1/3 + 1/6".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "1/2");
+
+        let mut caculator = Caculator::<Rational>::with_number("2^(1/2)".to_string());
+        assert!(caculator.evaluate().is_err());
+
+        let mut caculator = Caculator::<f64>::with_number("sqrt(16) + abs(-2)".to_string());
+        assert_eq!(caculator.evaluate()?, Value::Num(6.0));
+
+        let mut caculator = Caculator::<f64>::with_number("2i".to_string());
+        assert!(caculator.evaluate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_units() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("3 m + 20 cm", "16/5 m"),
+            ("20 cm + 3 m", "320 cm"),
+            ("60 km/h * 2.5 h to km", "150 km"),
+            ("5 ft to in", "60 in"),
+            ("2 km * 3", "6 km"),
+            ("10 m / 2 s", "5 m/s"),
+            ("3 m / 20 cm", "15"),
+            ("sqrt(9 m^2)", "3 m"),
+            ("2 kg * 3 m / s^2 to N", "6 N"),
+            ("2 m * 3 m", "6 m^2"),
+            ("2 m * 3 m * 4 m", "24 m^3"),
+            ("6 m / 2 s / 3 s", "1 m/s^2"),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        let mut caculator = Caculator::new("5 ft to m".to_string());
+        let r = caculator.evaluate()?;
+        assert!(matches!(r, Value::Quantity(ref q) if (q.value.re - 1.524).abs() < 1e-12));
+
+        for exp in ["3 m + 2 s", "1 + 1 m", "5 kg to m", "2^(1 m)"] {
+            let mut caculator = Caculator::new(exp.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert!(
+                matches!(
+                    err.downcast_ref::<CaculatorError>(),
+                    Some(CaculatorError::DimensionMismatch(..))
+                ),
+                "exp: {}",
+                exp
+            );
+        }
+
+        // exponents beyond `i8` overflow instead of wrapping
+        let t_cases = vec![
+            ("(m^127) * m", "number overflow in: m^127 * m at 8..9"),
+            (
+                "m^100 / m^-100",
+                "number overflow in: m^100 * m^100 at 6..7",
+            ),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.0.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_currency() -> anyhow::Result<()> {
+        let json = r#"{"date": "2026-10-01", "base": "USD", "rates": {"EUR": 0.8, "GBP": 0.75}}"#;
+        let rates = Arc::new(RateTable::from_json(json)?);
+
+        let mut caculator = Caculator::<Rational>::with_number("120 USD to EUR".to_string())
+            .with_rates(rates.clone());
+        assert_eq!(caculator.evaluate()?.to_string(), "96 EUR");
+        assert_eq!(caculator.rates_date(), Some("2026-10-01"));
+
+        let mut caculator = Caculator::<Rational>::with_number("10 EUR + 15 GBP".to_string())
+            .with_rates(rates.clone());
+        assert_eq!(caculator.evaluate()?.to_string(), "26 EUR");
+
+        let mut caculator =
+            Caculator::<Rational>::with_number("2 + 3".to_string()).with_rates(rates.clone());
+        caculator.evaluate()?;
+        assert_eq!(caculator.rates_date(), None);
+
+        for (exp, rates) in [("5 CHF to EUR", Some(rates.clone())), ("5 USD", None)] {
+            let mut caculator = Caculator::new(exp.to_string());
+            caculator.rates = rates;
+            let err = caculator.evaluate().unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<CaculatorError>(),
+                Some(CaculatorError::MissingRate(code)) if code == &exp[2..5]
+            ));
+        }
+
+        let mut caculator = Caculator::new("5 USD + 2 m".to_string()).with_rates(rates);
+        assert!(caculator.evaluate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dates() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("2026-12-25 - 2026-10-19", "67 days"),
+            ("1h 30min + 45min", "2h 15min"),
+            ("2026-12-25 + 90 days", "2027-03-25"),
+            ("2026-12-25T18:00 - 2026-12-25 09:30", "8h 30min"),
+            ("2026-03-01 - 1 day", "2026-02-28"),
+            ("(2027-01-01 - 2026-12-25) to h", "168 h"),
+            ("90 min to h", "3/2 h"),
+            ("2 * (1h 30min)", "3h"),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        let mut caculator = Caculator::new("now + 90 days - today".to_string());
+        let r = caculator.evaluate()?;
+        assert!(r.to_string().starts_with("90 days"), "{}", r);
+
+        for exp in [
+            "2026-12-25 + 2026-12-25",
+            "today + 3 m",
+            "2026-02-30",
+            "today * 2",
+        ] {
+            let mut caculator = Caculator::new(exp.to_string());
+            assert!(caculator.evaluate().is_err(), "exp: {} should fail", exp);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_angle_mode() -> anyhow::Result<()> {
+        let degrees = Context {
+            angle: AngleMode::Degrees,
+            ..Context::default()
+        };
+        let t_cases = vec![
+            ("sin(30)", degrees, 0.5),
+            ("cos(60deg)", Context::default(), 0.5),
+            ("sin((pi/6)rad)", degrees, 0.5),
+            ("tan(50grad)", Context::default(), 1.0),
+            ("asin(1)", degrees, 90.0),
+            (
+                "acos(0)",
+                Context {
+                    angle: AngleMode::Gradians,
+                    ..Context::default()
+                },
+                100.0,
+            ),
+            ("deg2rad(180)", degrees, std::f64::consts::PI),
+            ("rad2deg(pi)", Context::default(), 180.0),
+            ("grad2deg(100)", Context::default(), 90.0),
+            ("30deg", Context::default(), std::f64::consts::PI / 6.0),
+            ("0.5rad", degrees, 0.5 * 180.0 / std::f64::consts::PI),
+            ("arg(i)", degrees, 90.0),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::new(tc.0.to_string()).with_context(tc.1);
+            let r = caculator.calculate()?;
+            assert!((r - tc.2).abs() < 1e-12, "exp: {}, got {}", tc.0, r);
+        }
+
+        let mut caculator = Caculator::new("polar(2, 90)".to_string()).with_context(degrees);
+        assert_eq!(caculator.evaluate()?.to_string(), "2i");
+
+        let mut caculator = Caculator::<Rational>::with_number("30deg".to_string());
+        assert!(caculator.evaluate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_exponent_literals() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("1e21", "1e21"),
+            ("1e-20", "1e-20"),
+            ("2.5E+3 + 1", "2501"),
+            ("2e", "5.43656365691809"),
+            ("2exp(0)", "2"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::new(tc.0.to_string());
+            let r = caculator.evaluate()?.format(&Format::default());
+            assert_eq!(r, tc.1, "exp: {}", tc.0);
+        }
+
+        // a shortest result reads back as the same number
+        let mut caculator = Caculator::new("10^20 * 10".to_string());
+        let shown = caculator.evaluate()?.format(&Format::default());
+        let mut caculator = Caculator::new(shown);
+        assert_eq!(caculator.calculate()?, 1e21);
+
+        let mut caculator = Caculator::<Rational>::with_number("1.5e-3".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "3/2000");
+        Ok(())
+    }
+
+    #[test]
+    fn test_format() -> anyhow::Result<()> {
+        let sig = Format {
+            notation: Notation::Significant(4),
+            grouping: true,
+        };
+        let t_cases = vec![
+            ("0.1 + 0.2", Format::default(), "0.30000000000000004"),
+            ("0.1 + 0.2", sig, "0.3000"),
+            ("10^20", Format::default(), "100000000000000000000"),
+            ("10^20 * 10", Format::default(), "1e21"),
+            ("1234.5678 km", sig, "1,235 km"),
+            ("2/3 + i/3", sig, "0.6667+0.3333i"),
+            (
+                "(99 + 1) * (1 - 0.9) / 2^3",
+                Format {
+                    notation: Notation::Fraction(9),
+                    grouping: false,
+                },
+                "5/4",
+            ),
+            (
+                "m / 7",
+                Format {
+                    notation: Notation::Repeating(9),
+                    grouping: false,
+                },
+                "0.(142857) m",
+            ),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::new(tc.0.to_string());
+            assert_eq!(caculator.evaluate()?.format(&tc.1), tc.2, "exp: {}", tc.0);
+        }
+
+        let mut caculator = Caculator::<Rational>::with_number("1000000/3".to_string());
+        let r = caculator.evaluate()?;
+        assert_eq!(
+            r.format(&Format {
+                grouping: true,
+                ..Format::default()
+            }),
+            "1,000,000/3"
+        );
+        assert_eq!(
+            r.format(&Format {
+                notation: Notation::Fixed(2),
+                grouping: false
+            }),
+            "333333.33"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_radix() -> anyhow::Result<()> {
+        let mut caculator = Caculator::new("255 + 1/2".to_string());
+        let r = caculator.evaluate()?;
+        assert_eq!(r.radix(16)?, "FF.8");
+        assert_eq!(r.radix(2)?, "11111111.1");
+        assert_eq!(r.radix(8)?, "377.4");
+
+        let mut caculator = Caculator::<Rational>::with_number("-7/3".to_string());
+        let r = caculator.evaluate()?;
+        assert_eq!(r.radix(3)?, "-2.1");
+        assert_eq!(r.radix(10)?, format!("-2.{}", "3".repeat(16)));
+
+        for exp in ["1 + i", "3 m"] {
+            let mut caculator = Caculator::new(exp.to_string());
+            assert!(caculator.evaluate()?.radix(16).is_err(), "exp: {}", exp);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_intervals() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("9.81 ± 0.02", "9.81 ± 0.02"),
+            ("2 * (9.81 ± 0.02)", "19.62 ± 0.04"),
+            ("(1.5 ± 0.1) * (2 ± 0.2)", "3.0 ± 0.5"),
+            ("interval(1, 2) + interval(3, 4)", "[4, 6]"),
+            ("interval(1, 2) - interval(3, 4)", "[-3, -1]"),
+            ("-interval(1, 2) ^ 2", "[-4, -1]"),
+            ("interval(-1, 2) ^ 2", "[0, 4]"),
+            ("1 / interval(2, 4)", "[0.25, 0.5]"),
+            ("sqrt(interval(4, 9))", "[2, 3]"),
+            ("2interval(1, 2)", "[2, 4]"),
+            ("interval(1, 3)", "[1, 3]"),
+            ("mid(interval(1, 3))", "2"),
+            ("width(2 ± 0.5)", "1"),
+            ("abs(interval(-3, 2))", "[0, 3]"),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::new(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        let degrees = Context {
+            angle: AngleMode::Degrees,
+            ..Context::default()
+        };
+        let mut caculator =
+            Caculator::new("sin(interval(0, 180))".to_string()).with_context(degrees);
+        let Value::Interval(r) = caculator.evaluate()? else {
+            panic!("expected an interval");
+        };
+        assert!(r.lo.abs() < 1e-15 && r.hi == 1.0, "{}", r);
+
+        for exp in [
+            "1 / interval(-1, 1)",
+            "interval(2, 1)",
+            "sqrt(interval(-1, 1))",
+            "interval(1, 2) + i",
+        ] {
+            let mut caculator = Caculator::new(exp.to_string());
+            assert!(caculator.evaluate().is_err(), "exp: {} should fail", exp);
+        }
+
+        // intervals carry no unit
+        for exp in [
+            "interval(1, 2) m",
+            "(9.81 ± 0.02) m/s^2",
+            "2 m ± 0.1",
+            "interval(1, 2) + 3 m",
+            "interval(1 m, 2 m)",
+            "sqrt(interval(1, 4) m)",
+        ] {
+            let mut caculator = Caculator::new(exp.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert!(
+                matches!(
+                    err.downcast_ref::<CaculatorError>(),
+                    Some(CaculatorError::IntervalUnit(_))
+                ),
+                "exp: {} gave {}",
+                exp,
+                err
+            );
+        }
+        let mut caculator = Caculator::new("(9.81 ± 0.02) m/s^2".to_string());
+        let err = caculator.evaluate().unwrap_err();
+        assert_eq!(err.to_string(), "intervals cannot have units: m");
+        Ok(())
+    }
+
+    #[test]
+    fn test_matrices() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("[1, 2, 3]", "[1, 2, 3]"),
+            ("[[1, 2], [3, 4]] * [[1, 2], [3, 4]]", "[[7, 10], [15, 22]]"),
+            ("[[1, 2], [3, 4]] * [1, 1]", "[3, 7]"),
+            ("[1, 2, 3] + [3, 4, 5]", "[4, 6, 8]"),
+            ("2[1, 2, 3]", "[2, 4, 6]"),
+            ("-[1, 2, 3] / 2", "[-0.5, -1, -1.5]"),
+            ("[1, 2] .* [3, 4]", "[3, 8]"),
+            ("[2, 1] - [1, 2]", "[1, -1]"),
+            ("[1, 2] - [1, 2]", "[0, 0]"),
+            ("2 * [3, 1]", "[6, 2]"),
+            ("transpose([[1, 2], [3, 4]]) * [1, 1]", "[4, 6]"),
+            ("[1, 2, 3] .* [4, 5, 6]", "[4, 10, 18]"),
+            ("[4, 9] ./ [2, 3]", "[2, 3]"),
+            ("sqrt([4, 9, 16])", "[2, 3, 4]"),
+            ("dot([1, 2, 3], [4, 5, 6])", "32"),
+            ("dot([1, 2], [3, 4])", "11"),
+            ("cross([1, 0, 0], [0, 1, 0])", "[0, 0, 1]"),
+            ("norm([3, 4])", "5"),
+            ("transpose([[1, 2], [3, 4]])", "[[1, 3], [2, 4]]"),
+            ("[[1, 1], [0, 1]] ^ 3", "[[1, 3], [0, 1]]"),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        // the length of a complex vector uses the moduli of its entries
+        for (exp, expected) in [("norm([3i, 4, 0])", "5"), ("norm([1+i, 1-i])", "2")] {
+            let mut caculator = Caculator::new(exp.to_string());
+            assert_eq!(caculator.evaluate()?.to_string(), expected, "exp: {}", exp);
+        }
+
+        // exact elimination over rationals
+        let t_cases = vec![
+            ("det([[1, 2], [3, 4]])", "-2"),
+            ("inv([[1, 2], [3, 4]])", "[[-2, 1], [3/2, -1/2]]"),
+            ("[[2, 1], [1, -1]] \\ [5, 1]", "[2, 1]"),
+            ("inv([[1, 2], [3, 4]]) * [1, 1]", "[-1, 1]"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        let t_cases = vec![
+            ("[1, 2] + [1, 2, 3]", "shape mismatch: [2] and [3]"),
+            (
+                "[[1, 2], [3, 4]] * [1, 2, 3]",
+                "shape mismatch: [2x2] and [3]",
+            ),
+            ("inv([[1, 2], [2, 4]])", "matrix is singular"),
+            ("[[1, 2], 3]", "shape mismatch: [2] and 1"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_statistics() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("sum([1, 2, 3, 4])", "10"),
+            ("mean(1, 2, 3, 4)", "2.5"),
+            ("median([3, 1, 2])", "2"),
+            ("median([4, 1, 3, 2])", "2.5"),
+            ("mode([1, 2, 2, 3])", "2"),
+            ("variance([2, 4, 4, 4, 5, 5, 7, 9])", "4.571428571428571"),
+            ("stdev([1, 3])", "1.4142135623730951"),
+            ("percentile([1, 2, 3, 4, 5], 90)", "4.6"),
+            ("min(3, -1, 2)", "-1"),
+            ("max([3, -1, 2])", "3"),
+            ("count([5, 5, 5])", "3"),
+            ("linreg([1, 2, 3], [3, 5, 7])", "[2, 1]"),
+            ("[1\n2\n3\n]", "[1, 2, 3]"),
+            ("mean([10\r\n20\r\n30])", "20"),
+            ("sum([1\t2\t3])", "6"),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {:?}", tc.0);
+        }
+
+        let mut caculator = Caculator::<Rational>::with_number("mean([1, 2, 4])".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "7/3");
+
+        for exp in [
+            "variance([1])",
+            "percentile([1, 2], 150)",
+            "linreg([1, 2], [1, 2, 3])",
+            "mean(1 m, 2 m)",
+        ] {
+            let mut caculator = Caculator::<f64>::with_number(exp.to_string());
+            assert!(caculator.evaluate().is_err(), "exp: {} should fail", exp);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("diff(x^2, x)", "2*x"),
+            ("diff(3x^2 + 2x + 1, x)", "6*x + 2"),
+            ("diff(x^3, x)", "3*x^2"),
+            ("diff(diff(x^3, x), x)", "6*x"),
+            ("diff(sin(x), x)", "cos(x)"),
+            ("diff(exp(2x), x)", "2*exp(2*x)"),
+            ("diff(ln(x), x)", "1/x"),
+            ("diff(x(x + 1), x)", "2*x + 1"),
+            ("diff(t^2 + t, t)", "2*t + 1"),
+            ("diff(5, x)", "0"),
+            ("2 * diff(x^2, x)", "4*x"),
+            ("diff(x^2, x) + 1", "2*x + 1"),
+            ("diff(x*y, x)", "y"),
+            ("diff(a*x^2, x)", "2*a*x"),
+            ("diff(m*g*h, h)", "g*m"),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        for exp in [
+            "diff(floor(x), x)",
+            "diff(x^2, 2)",
+            "diff(x^2)",
+            "diff(x^2, x",
+        ] {
+            let mut caculator = Caculator::<f64>::with_number(exp.to_string());
+            assert!(caculator.evaluate().is_err(), "exp: {} should fail", exp);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_simplify() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("simplify(2*x + 3*x - x)", "4*x"),
+            ("simplify(x*y/x)", "y"),
+            ("simplify(a + b - a + 2b)", "3*b"),
+            ("simplify(x*x*x / x^2)", "x"),
+            ("simplify((x^2)^3 + 0)", "x^6"),
+            ("simplify((x^2)^0.5)", "abs(x)"),
+            ("simplify((x^2)^1.5)", "abs(x)^3"),
+            ("simplify((x^4)^0.5)", "x^2"),
+            ("simplify((x^3)^(1/3))", "x"),
+            ("simplify((x^0.5)^0.5)", "(x^0.5)^0.5"),
+            ("simplify(2(x + 1) - 2(x + 1))", "0"),
+            ("simplify(x - 1 + 3)", "x + 2"),
+            ("simplify(sin(x)^2 * 1)", "sin(x)^2"),
+            ("simplify(1 + 2)", "3"),
+            // unit names are variables unless they follow a number
+            ("simplify(t + t)", "2*t"),
+            ("simplify(s*s)", "s^2"),
+            ("simplify(m*g*h / m)", "g*h"),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        // exact coefficients stay exact
+        let mut caculator = Caculator::<Rational>::with_number("simplify(x/3 + x/6)".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "1/2*x");
+
+        for exp in ["simplify()", "simplify(x, y)", "simplify(x + 1 USD)"] {
+            let mut caculator = Caculator::<f64>::with_number(exp.to_string());
+            assert!(caculator.evaluate().is_err(), "exp: {} should fail", exp);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("solve(x^2 = 4, x)", "2"),
+            ("solve(2x + 1 = 7, x)", "3"),
+            ("solve(exp(y) = 10, y)", "2.3025850929940455"),
+            ("solve(x^2 = 4, x, -5, 5)", "[-2, 2]"),
+            ("solve(x^3 = x, x, -2, 2)", "[-1, 0, 1]"),
+            ("root(cos(x) - x, x, 1)", "0.7390851332151607"),
+            ("root(x^3 - 2x - 5, x, 2)", "2.0945514815423265"),
+            ("root(x^2 = 2, x, -1)", "-1.414213562373095"),
+            ("1 + solve(x - 1 = 0, x)", "2"),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        // the sine takes degrees
+        let degrees = Context {
+            angle: AngleMode::Degrees,
+            ..Context::default()
+        };
+        let mut caculator =
+            Caculator::<f64>::with_number("solve(sin(x) = 0.5, x, 0, 180)".to_string())
+                .with_context(degrees);
+        let Value::Matrix(r) = caculator.evaluate()? else {
+            panic!("expected a vector");
+        };
+        assert!((r.data[0] - 30.0).abs() < 1e-9 && (r.data[1] - 150.0).abs() < 1e-9);
+
+        let t_cases = vec![
+            ("solve(x^2 = -1, x)", "no root found for: x^2 = -1"),
+            ("solve(x = 1 = 2, x)", "invalid expression: x = 1 = 2"),
+            ("solve(x^2, 3)", "invalid expression: 3"),
+            ("root(x, x)", "function: root need 3 arguments"),
+            ("2 = 1", "unsupported operator: ="),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_integrate() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("integrate(x^2, x, 0, 3)", 9.0, 1e-12),
+            ("integrate(sin(t), t, 0, pi)", 2.0, 1e-12),
+            (
+                "integrate(exp(-x^2), x, -5, 5)",
+                std::f64::consts::PI.sqrt(),
+                1e-10,
+            ),
+            ("integrate(1/x, x, 1, e)", 1.0, 1e-12),
+            ("nderiv(x^3, x, 2)", 12.0, 1e-9),
+            ("nderiv(sin(x), x, 0)", 1.0, 1e-10),
+            ("2 * integrate(x, x, 0, 1)", 1.0, 1e-12),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let r = caculator.calculate()?;
+            assert!((r - tc.1).abs() < tc.2, "exp: {} = {}", tc.0, r);
+            // the estimate is a conservative bound
+            let error = caculator.error_estimate().unwrap();
+            assert!(error < 1e-8, "exp: {} error {}", tc.0, error);
+        }
+
+        let mut caculator = Caculator::<f64>::with_number("1 + 2".to_string());
+        caculator.evaluate()?;
+        assert_eq!(caculator.error_estimate(), None);
+
+        let t_cases = vec![
+            ("integrate(1/x, x, -1, 1)", "no convergence for: 1/x"),
+            ("integrate(x, x, 0)", "function: integrate need 4 arguments"),
+            ("nderiv(x, x, i)", "result is not a real number: i"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::new(tc.0.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_iteration() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("sum(i, 1, 100, i^2)", "338350"),
+            ("prod(k, 1, 10, k)", "3628800"),
+            ("sum(k, 1, 0, k)", "0"),
+            ("prod(k, 5, 1, k)", "1"),
+            ("sum(k, -2, 2, k^3)", "0"),
+            ("sum(i, 1, 3, sum(j, 1, i, j))", "10"),
+            ("2 * sum(n, 1, 3, n) + 1", "13"),
+            ("sum([1, 2, 3])", "6"),
+            ("sum(1, 2, 3, 4)", "10"),
+        ];
+
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        // the loop variable shadows the imaginary unit only inside the sum
+        let mut caculator = Caculator::new("sum(i, 1, 4, i) + i".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "10+i");
+
+        // exact partial sums of the harmonic series
+        let mut caculator = Caculator::<Rational>::with_number("sum(k, 1, 10, 1/k)".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "7381/2520");
+
+        let t_cases = vec![
+            ("sum(k, 1, 10000000, k)", "more than 1000000 iterations"),
+            ("sum(k, 1, 2.5, k)", "invalid expression: 2.5"),
+            ("prod(k, 1, 3)", "function: prod need 4 arguments"),
+            ("sum(k, 0, 2, 1/k)", "divide by zero"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_systems() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("2x + y = 5; x - y = 1", "[2, 1]"),
+            ("x + y + z = 6; 2y = z + 1; x = 3z - 8;", "[1, 2, 3]"),
+            ("a/2 = 1; a + b = 0; 2a + 2b = 0", "[2, -2]"),
+            ("2m + s = 5; m - s = 1", "[2, 1]"),
+            ("V = 2A; V + A = 6", "[4, 2]"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        // exact numbers give exact solutions
+        let t_cases = vec![
+            ("3x + y = 1; x - y = 1", "[1/2, -1/2]"),
+            ("3(x + 1) = 2y; x = y - 2", "[1, 3]"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        let t_cases = vec![
+            (
+                "x + y = 1; 2x + 2y = 2",
+                "system has infinitely many solutions",
+            ),
+            ("x + y = 1; x + y = 2", "system has no solution"),
+            ("x = 1; x = 2; y = 0", "system has no solution"),
+            ("x + y = 1", "unknown identifier: x"),
+            ("x*y = 1; x = 2", "not a linear equation: x*y = 1"),
+            ("x = 1;; y = 2", "invalid expression: x = 1;; y = 2"),
+            ("1; 2", "invalid expression: 1; 2"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        // rounding in decimal coefficients does not decide solvability
+        let dependent = "0.1x + 0.2y = 0.3; 0.3x + 0.6y = 0.9";
+        let inconsistent = "0.1x + 0.2y = 0.3; 0.3x + 0.6y = 1";
+        let many = "system has infinitely many solutions";
+        let none = "system has no solution";
+        for (exp, expected) in [(dependent, many), (inconsistent, none)] {
+            let err = Caculator::<f64>::with_number(exp.to_string()).evaluate();
+            assert_eq!(err.unwrap_err().to_string(), expected, "exp: {}", exp);
+            let err = Caculator::<Complex>::with_number(exp.to_string()).evaluate();
+            assert_eq!(err.unwrap_err().to_string(), expected, "exp: {}", exp);
+            let err = Caculator::<Rational>::with_number(exp.to_string()).evaluate();
+            assert_eq!(err.unwrap_err().to_string(), expected, "exp: {}", exp);
+        }
+        let mut caculator =
+            Caculator::<f64>::with_number("0.1x + 0.2y = 0.5; 0.3x - 0.1y = 0.1".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "[1, 2]");
+        Ok(())
+    }
+
+    #[test]
+    fn test_polynomials() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("polyval([1, -3, 2], 3)", "2"),
+            ("polyval([1, 0, 0], [1, 2, 3])", "[1, 4, 9]"),
+            ("polymul([1, 1], [1, -1])", "[1, 0, -1]"),
+            ("polydiv([1, 0, -1], [1, 1])", "[1, -1]"),
+            ("polyrem([1, 0, 1], [1, 1])", "[2]"),
+            ("polyroots([1, -3, 2])", "[1, 2]"),
+            (
+                "polyroots([1, 0, 0, -1])",
+                "[-0.5-0.8660254037844386i, -0.5+0.8660254037844386i, 1]",
+            ),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::with_number(tc.0.to_string());
+            let r: Value<Complex> = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {:?}", tc.0);
+        }
+
+        let mut caculator = Caculator::<Rational>::with_number(
+            "polyfit([-1, 0, 1, 2], [3, 1, 3, 9], 2)".to_string(),
+        );
+        assert_eq!(caculator.evaluate()?.to_string(), "[2, 0, 1]");
+
+        let t_cases = vec![
+            ("polyroots([1, 0, 1])", "result is not a real number: -i"),
+            ("polyroots([5])", "no root found for: [5]"),
+            ("polyroots([0, 2.5])", "no root found for: [2.5]"),
+            ("polydiv([1, 2], [0])", "divide by zero"),
+            ("polyfit([1, 2], [1, 2], 0.5)", "invalid expression: 0.5"),
+            ("polyval([1, 2])", "function: polyval need 2 arguments"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_finance() -> anyhow::Result<()> {
+        let cents = Format {
+            notation: Notation::Fixed(2),
+            grouping: false,
+        };
+        let t_cases = vec![
+            ("pmt(0.05/12, 360, 200000)", "-1073.64"),
+            ("pmt(0.05/12, 360, 200000, 0, 1)", "-1069.19"),
+            ("pv(0.08/12, 240, -500)", "59777.15"),
+            ("fv(0.06/12, 10, -200, -500, 1)", "2581.40"),
+            ("nper(0.01, -100, -1000, 10000, 1)", "59.67"),
+            ("rate(48, -200, 8000)", "0.01"),
+            ("100 * rate(48, -200, 8000)", "0.77"),
+            ("npv(0.1, -10000, 3000, 4200, 6800)", "1188.44"),
+            ("npv(0.1, [3000, 4200, 6800]) - 10000", "1307.29"),
+            ("irr([-70000, 12000, 15000, 18000, 21000, 26000])", "0.09"),
+            (
+                "xnpv(0.09, [-10000, 2750, 4250, 3250, 2750], 2008-01-01, 2008-03-01, \
+                 2008-10-30, 2009-02-15, 2009-04-01)",
+                "2086.65",
+            ),
+            ("xnpv(0.09, [-10000, 2750], [0, 365])", "-7477.06"),
+            (
+                "amort(0.01, 2, 1000)",
+                "[[1.00, -507.51, -10.00, -497.51, 502.49], [2.00, -507.51, -5.02, -502.49, 0.00]]",
+            ),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.format(&cents), tc.1, "exp: {}", tc.0);
+        }
+
+        // decimal inputs give the same cents in exact mode
+        let mut caculator =
+            Caculator::<Rational>::with_number("pmt(0.05/12, 360, 200000)".to_string());
+        assert_eq!(caculator.evaluate()?.format(&cents), "-1073.64");
+
+        let t_cases = vec![
+            ("pmt(0.05, 10)", "function: pmt need 3 arguments"),
+            ("amort(0.01, 2.5, 1000)", "invalid expression: 2.5"),
+            ("irr([100, 200])", "no root found for: irr"),
+            ("nper(0.1, -10, 1000)", "no root found for: nper"),
+            ("xnpv(0.1, [1, 2], [0])", "shape mismatch: [2] and [1]"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<f64>::with_number(tc.0.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_number_theory() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("gcd(12, 18)", "6"),
+            ("gcd(12, 18, -8)", "2"),
+            ("gcd([12, 18, 27])", "3"),
+            ("gcd(-4)", "4"),
+            ("lcm(4, 6, 10)", "60"),
+            ("isprime(97)", "1"),
+            ("isprime(1)", "0"),
+            ("isprime(-7)", "0"),
+            ("factor(360)", "[[2, 3], [3, 2], [5, 1]]"),
+            ("factor(97)", "[[97, 1]]"),
+            ("nextprime(100)", "101"),
+            ("nextprime(-5)", "2"),
+            ("totient(36)", "12"),
+            ("modinv(3, 11)", "4"),
+            ("powmod(2, 100, 1000000007)", "976371285"),
+            ("powmod(3, -1, 11)", "4"),
+            ("17 % 5", "2"),
+            ("-7 % 3", "2"),
+            ("7 % -3", "-2"),
+            ("1 + 10 % 4 * 3", "7"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        // the default backend works on whole complex numbers too
+        let mut caculator = Caculator::<Complex>::with_number("gcd(2^40, 6^20)".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "1048576");
+
+        // floats hold integers exactly only up to 2^53
+        let mut caculator = Caculator::<Rational>::with_number("isprime(2^61 - 1)".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "1");
+        let mut caculator = Caculator::<f64>::with_number("isprime(2^53 - 1)".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "0");
+        let inexact = |err: anyhow::Error| {
+            matches!(
+                err.downcast_ref::<CaculatorError>(),
+                Some(CaculatorError::Inexact(_))
+            )
+        };
+        for exp in [
+            "isprime(2305843009213693951)",
+            "isprime(2^61 - 1)",
+            "powmod(3, 2^60 + 1, 2^61 - 1)",
+        ] {
+            let err = Caculator::<f64>::with_number(exp.to_string()).evaluate();
+            assert!(inexact(err.unwrap_err()), "exp: {}", exp);
+            let err = Caculator::<Complex>::with_number(exp.to_string()).evaluate();
+            assert!(inexact(err.unwrap_err()), "exp: {}", exp);
+        }
+
+        let t_cases = vec![
+            ("gcd(1.5, 2)", "not an integer: gcd(3/2, 2) at 0..3"),
+            ("1 + 7.5 % 2", "not an integer: 15/2 % 2 at 8..9"),
+            ("factor(1)", "argument out of range: factor(1) at 0..6"),
+            ("modinv(6, 9)", "no modular inverse: modinv(6, 9) at 0..6"),
+            ("totient(0)", "argument out of range: totient(0) at 0..7"),
+            ("5 % 0", "divide by zero"),
+            (
+                "powmod(6, -1, 9)",
+                "no modular inverse: powmod(6, -1, 9) at 0..6",
+            ),
+            ("powmod(2, 3)", "function: powmod need 3 arguments"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.0.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_combinatorics() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("nCr(5, 2)", "10"),
+            ("binomial(5, 7)", "0"),
+            ("binomial(-3, 2)", "6"),
+            ("binomial(-3, 3)", "-10"),
+            ("nPr(5, 2)", "20"),
+            ("nCr(60, 30)", "118264581564861424"),
+            ("nCr(100, 50)", "100891344545564193334812497256"),
+            ("multinomial(2, 3, 4)", "1260"),
+            ("multinomial([1, 1, 1])", "6"),
+            ("catalan(10)", "16796"),
+            ("fib(90)", "2880067194370816120"),
+            ("fib(-6)", "-8"),
+            ("fib(-7)", "13"),
+            ("stirling1(5, 2)", "50"),
+            ("stirling2(10, 4)", "34105"),
+            ("derangements(10)", "1334961"),
+            ("nCr(10, 3) / nPr(10, 3)", "1/6"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.0.to_string());
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        // counts beyond i128 are rounded only once, at the end
+        let mut caculator = Caculator::<f64>::with_number("nCr(1000, 500)".to_string());
+        let r = caculator.evaluate()?.to_number()?.to_f64().unwrap();
+        assert_eq!(r, 2.7028824094543655e299);
+
+        let t_cases = vec![
+            ("nCr(200, 100)", "number overflow in: nCr(200, 100) at 0..3"),
+            ("fib(2000)", "number overflow in: fib(2000) at 0..3"),
+            ("nCr(5.5, 2)", "not an integer: nCr(11/2, 2) at 0..3"),
+            ("nPr(-1, 2)", "argument out of range: nPr(-1, 2) at 0..3"),
+            ("catalan(1, 2)", "function: catalan need 1 arguments"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.0.to_string());
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_random() -> anyhow::Result<()> {
+        let seeded = |exp: &str, seed: u64| {
+            Caculator::<f64>::with_number(exp.to_string()).with_context(Context {
+                seed: Some(seed),
+                ..Context::default()
+            })
+        };
+        let draw = |exp: &str, seed: u64| -> anyhow::Result<f64> {
+            match seeded(exp, seed).evaluate()? {
+                Value::Num(x) => Ok(x),
+                v => panic!("expected a number: {}", v),
+            }
+        };
+
+        // the same seed gives the same numbers, every call a new one
+        assert_eq!(draw("rand()", 1)?, draw("rand()", 1)?);
+        assert_ne!(draw("rand()", 1)?, draw("rand()", 2)?);
+        assert_ne!(draw("rand() - rand()", 1)?, 0.0);
+        let mut caculator = seeded("rand()", 3);
+        assert_eq!(caculator.evaluate()?, caculator.evaluate()?);
+
+        for seed in 0..20 {
+            assert!((0.0..1.0).contains(&draw("rand()", seed)?));
+            let die = draw("randint(1, 6)", seed)?;
+            assert!(die.fract() == 0.0 && (1.0..=6.0).contains(&die));
+            // each iteration draws again
+            let total = draw("sum(k, 1, 10, rand())", seed)?;
+            assert!((0.0..10.0).contains(&total) && total != 10.0 * draw("rand()", seed)?);
+            assert!([2.0, 4.0, 6.0].contains(&draw("choice([2, 4, 6])", seed)?));
+            assert!(draw("randn(100, 0.001)", seed)?.abs() > 99.0);
+        }
+        assert_eq!(draw("choice(7)", 5)?, 7.0);
+
+        let t_cases = vec![
+            ("randint(3, 1)", "invalid expression: randint(3, 1)"),
+            ("randint(1.5, 2)", "invalid expression: randint(1.5, 2)"),
+            ("rand(1)", "function: rand need 0 arguments"),
+            ("randn(1)", "function: randn need 2 arguments"),
+            ("randn(0, -1)", "invalid expression: randn(0, -1)"),
+        ];
+        for tc in t_cases {
+            let err = seeded(tc.0, 0).evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_programmer() -> anyhow::Result<()> {
+        let context = |ty: &str, overflow: OverflowMode| Context {
+            word: Some(WordMode {
+                ty: IntType::from_name(ty).unwrap(),
+                overflow,
+            }),
+            ..Context::default()
+        };
+        let t_cases = vec![
+            ("i8", OverflowMode::Wrapping, "127 + 1", "-128"),
+            ("i8", OverflowMode::Saturating, "127 + 1", "127"),
+            ("i8", OverflowMode::Wrapping, "-(-128)", "-128"),
+            ("u8", OverflowMode::Wrapping, "0 - 1", "255"),
+            ("u8", OverflowMode::Saturating, "3 - 5", "0"),
+            ("u8", OverflowMode::Wrapping, "300", "44"),
+            ("i32", OverflowMode::Wrapping, "7 / 2", "3"),
+            ("i32", OverflowMode::Wrapping, "-7 / 2", "-3"),
+            ("i32", OverflowMode::Wrapping, "2^31", "-2147483648"),
+            ("u16", OverflowMode::Checked, "2(3 + 4)", "14"),
+            (
+                "u64",
+                OverflowMode::Wrapping,
+                "2^64 - 1",
+                "18446744073709551615",
+            ),
+            ("i8", OverflowMode::Wrapping, "not(5)", "-6"),
+            ("u8", OverflowMode::Wrapping, "not(5)", "250"),
+            ("u8", OverflowMode::Wrapping, "shl(255, 4)", "240"),
+            ("i16", OverflowMode::Wrapping, "or(256, 12)", "268"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.2.to_string())
+                .with_context(context(tc.0, tc.1));
+            let r = caculator.evaluate()?;
+            assert_eq!(r.to_string(), tc.3, "{} {:?}: {}", tc.0, tc.1, tc.2);
+        }
+
+        // bitwise functions work on i64 outside programmer mode
+        let mut caculator = Caculator::<f64>::with_number("xor(12, 10)".to_string());
+        assert_eq!(caculator.evaluate()?.to_string(), "6");
+
+        let t_cases = vec![
+            ("i8", "100 + 100", "number overflow in: 100 + 100 at 4..5"),
+            ("u8", "-1", "number overflow in: -1 at 0..1"),
+            ("u8", "256", "number overflow in: 256"),
+            ("i32", "7 / 0", "divide by zero"),
+            ("i32", "1.5 + 1", "not an integer: 3/2"),
+            ("i32", "shr(1, 32)", "invalid expression: shr(1, 32)"),
+        ];
+        for tc in t_cases {
+            let mut caculator = Caculator::<Rational>::with_number(tc.1.to_string())
+                .with_context(context(tc.0, OverflowMode::Checked));
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.2, "{}: {}", tc.0, tc.1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_strict() -> anyhow::Result<()> {
+        let strict = Context {
+            strict: true,
+            ..Context::default()
+        };
+
+        // without the option the garbage passes through
+        let mut caculator = Caculator::<f64>::with_number("10^400".to_string());
+        assert_eq!(caculator.calculate()?, f64::INFINITY);
+
+        let t_cases = vec![
+            ("1 + 10^400", "number overflow in: 10 ^ 400 at 6..7"),
+            ("2 * exp(1000)", "number overflow in: exp(1000) at 4..7"),
+            ("sqrt(-1) + 1", "result is not a number: sqrt(-1) at 0..4"),
+            ("ln(0)", "result is not a number: ln(0) at 0..2"),
+            (
+                "(10^300)(10^300)",
+                "number overflow in: 1e300 * 1e300 at 8..9",
+            ),
+        ];
+        for tc in t_cases {
+            let mut caculator =
+                Caculator::<f64>::with_number(tc.0.to_string()).with_context(strict);
+            let err = caculator.evaluate().unwrap_err();
+            assert_eq!(err.to_string(), tc.1, "exp: {}", tc.0);
+        }
+
+        let mut caculator =
+            Caculator::<f64>::with_number("1 - 10^308 * 10".to_string()).with_context(strict);
+        assert!(matches!(
+            caculator
+                .evaluate()
+                .unwrap_err()
+                .downcast::<CaculatorError>()?,
+            CaculatorError::Overflow {
+                span: Some(Span { start: 11, end: 12 }),
+                ..
+            }
+        ));
+
+        // exact overflow is located even without the option
+        let mut caculator = Caculator::<Rational>::with_number("1 + 10^20 * 10^20".to_string());
+        assert_eq!(
+            caculator.evaluate().unwrap_err().to_string(),
+            "number overflow in: 100000000000000000000 * 100000000000000000000 at 10..11"
+        );
+
+        let mut caculator = Caculator::new("sqrt(-1)".to_string()).with_context(strict);
+        assert_eq!(caculator.evaluate()?.to_string(), "i");
+        Ok(())
+    }
 }
@@ -0,0 +1,178 @@
+// Exact counting. Every function works on big integers and gives up with
+// `None` once a count passes `2^MAX_BITS`, which no backend can hold.
+
+use crate::bigint::BigUint;
+
+// `f64::MAX` is just below `2^1024`
+const MAX_BITS: u64 = 1024;
+
+// the most table entries the Stirling numbers fill in
+const MAX_ENTRIES: u64 = 10_000_000;
+
+/// True for the counting functions, like `nCr(n, k)`.
+pub fn is_combinatorial(name: &str) -> bool {
+    matches!(
+        name,
+        "nCr"
+            | "nPr"
+            | "binomial"
+            | "multinomial"
+            | "catalan"
+            | "fib"
+            | "stirling1"
+            | "stirling2"
+            | "derangements"
+    )
+}
+
+// `x` while it is below the limit; the counts only grow, so a larger
+// intermediate means a larger result
+fn bounded(x: BigUint) -> Option<BigUint> {
+    (x.bits() <= MAX_BITS).then_some(x)
+}
+
+/// The ways to choose `k` of `n` things, `n! / (k! (n-k)!)`.
+pub fn binomial(n: u64, k: u64) -> Option<BigUint> {
+    if k > n {
+        return Some(BigUint::zero());
+    }
+    // each step is again a binomial coefficient, so the division is exact
+    let k = k.min(n - k);
+    (1..=k).try_fold(BigUint::from(1), |c, i| {
+        bounded(c.mul_small(n - k + i).div_small(i).0)
+    })
+}
+
+/// The ways to arrange `k` of `n` things in order, `n! / (n-k)!`.
+pub fn permutations(n: u64, k: u64) -> Option<BigUint> {
+    if k > n {
+        return Some(BigUint::zero());
+    }
+    (n - k + 1..=n).try_fold(BigUint::from(1), |p, i| bounded(p.mul_small(i)))
+}
+
+/// The ways to split `k1 + k2 + ...` things into groups of these sizes.
+pub fn multinomial(ks: &[u64]) -> Option<BigUint> {
+    let mut total = 0u64;
+    let mut m = BigUint::from(1);
+    for &k in ks {
+        total = total.checked_add(k)?;
+        m = bounded(m.mul(&binomial(total, k)?))?;
+    }
+    Some(m)
+}
+
+/// The `n`-th Catalan number, `binomial(2n, n) / (n + 1)`.
+pub fn catalan(n: u64) -> Option<BigUint> {
+    // C(i + 1) = C(i) 2 (2i + 1) / (i + 2), an exact division
+    (0..n).try_fold(BigUint::from(1), |c, i| {
+        bounded(c.mul_small(2 * (2 * i + 1)).div_small(i + 2).0)
+    })
+}
+
+/// The `n`-th Fibonacci number, starting `0, 1, 1, 2`.
+pub fn fib(n: u64) -> Option<BigUint> {
+    let (mut a, mut b) = (BigUint::zero(), BigUint::from(1));
+    for _ in 0..n {
+        (a, b) = (b.clone(), bounded(a.add(&b))?);
+    }
+    Some(a)
+}
+
+/// The permutations of `n` things that leave none in place.
+pub fn derangements(n: u64) -> Option<BigUint> {
+    // D(n) = (n - 1) (D(n - 1) + D(n - 2))
+    let (mut a, mut b) = (BigUint::from(1), BigUint::zero());
+    if n == 0 {
+        return Some(a);
+    }
+    for i in 2..=n {
+        (a, b) = (b.clone(), bounded(a.add(&b).mul_small(i - 1))?);
+    }
+    Some(b)
+}
+
+/// Unsigned Stirling numbers of the first kind: the permutations of `n`
+/// things with `k` cycles.
+pub fn stirling1(n: u64, k: u64) -> Option<BigUint> {
+    stirling(n, k, |j, _| j)
+}
+
+/// Stirling numbers of the second kind: the ways to split `n` things into
+/// `k` nonempty groups.
+pub fn stirling2(n: u64, k: u64) -> Option<BigUint> {
+    stirling(n, k, |_, i| i)
+}
+
+// fills in the table by `s(j+1, i) = coef(j, i) s(j, i) + s(j, i-1)`, keeping
+// only the entries that lead to `s(n, k)`
+fn stirling(n: u64, k: u64, coef: fn(u64, u64) -> u64) -> Option<BigUint> {
+    if k > n {
+        return Some(BigUint::zero());
+    }
+    if n.checked_mul(k.min(n - k + 1))? > MAX_ENTRIES {
+        return None;
+    }
+    let mut row = vec![BigUint::zero(); k as usize + 1];
+    row[0] = BigUint::from(1);
+    for j in 0..n {
+        let lo = k.saturating_sub(n - j - 1).max(1);
+        for i in (lo..=k.min(j + 1)).rev() {
+            let i = i as usize;
+            let s = row[i].mul_small(coef(j, i as u64)).add(&row[i - 1]);
+            row[i] = bounded(s)?;
+        }
+        // only `s(0, 0)` is 1 in the first column
+        row[0] = BigUint::zero();
+    }
+    row.pop()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(x: Option<BigUint>) -> String {
+        x.map_or("none".to_string(), |x| x.to_string())
+    }
+
+    #[test]
+    fn test_choices() {
+        assert_eq!(count(binomial(5, 2)), "10");
+        assert_eq!(count(binomial(5, 7)), "0");
+        assert_eq!(count(binomial(0, 0)), "1");
+        assert_eq!(count(binomial(100, 50)), "100891344545564193334812497256");
+        assert_eq!(count(binomial(1 << 40, 1)), "1099511627776");
+        assert_eq!(count(binomial(2000, 1000)), "none");
+        assert_eq!(count(permutations(5, 2)), "20");
+        assert_eq!(count(permutations(25, 25)), "15511210043330985984000000");
+        assert_eq!(count(multinomial(&[2, 3, 4])), "1260");
+        assert_eq!(count(multinomial(&[])), "1");
+        let catalans: Vec<String> = (0..8).map(|n| count(catalan(n))).collect();
+        assert_eq!(catalans, ["1", "1", "2", "5", "14", "42", "132", "429"]);
+    }
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(count(fib(0)), "0");
+        assert_eq!(count(fib(10)), "55");
+        assert_eq!(
+            count(fib(200)),
+            "280571172992510140037611932413038677189525"
+        );
+        assert_eq!(count(fib(2000)), "none");
+        let derangements: Vec<String> = (0..7).map(|n| count(derangements(n))).collect();
+        assert_eq!(derangements, ["1", "0", "1", "2", "9", "44", "265"]);
+        assert_eq!(count(stirling1(5, 2)), "50");
+        assert_eq!(count(stirling1(4, 4)), "1");
+        assert_eq!(count(stirling1(3, 0)), "0");
+        assert_eq!(count(stirling1(0, 0)), "1");
+        assert_eq!(count(stirling2(5, 2)), "15");
+        assert_eq!(count(stirling2(10, 4)), "34105");
+        assert_eq!(count(stirling2(6, 7)), "0");
+        assert_eq!(count(stirling2(1 << 40, 1 << 39)), "none");
+        // the first kind sums to n!
+        let total = (0..=6).fold(BigUint::zero(), |acc, k| acc.add(&stirling1(6, k).unwrap()));
+        assert_eq!(total.to_string(), "720");
+    }
+}
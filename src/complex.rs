@@ -0,0 +1,331 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// parts smaller than this (relative to the magnitude) are treated as rounding noise
+const NOISE: f64 = 1e-15;
+
+/// A complex number in rectangular form.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const I: Complex = Complex { re: 0.0, im: 1.0 };
+
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn from_polar(r: f64, theta: f64) -> Self {
+        Self::new(r * theta.cos(), r * theta.sin())
+    }
+
+    pub fn is_real(&self) -> bool {
+        self.im == 0.0
+    }
+
+    /// Returns the real part if the imaginary part is zero or rounding noise.
+    pub fn to_real(&self) -> Option<f64> {
+        if self.is_real() || self.im.abs() <= NOISE * self.abs() {
+            return Some(self.re);
+        }
+        None
+    }
+
+    pub fn abs(&self) -> f64 {
+        if self.is_real() {
+            return self.re.abs();
+        }
+        self.re.hypot(self.im)
+    }
+
+    pub fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn conj(&self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn sqrt(&self) -> Self {
+        if self.is_real() {
+            if self.re >= 0.0 {
+                return Self::from(self.re.sqrt());
+            }
+            return Self::new(0.0, (-self.re).sqrt());
+        }
+        Self::from_polar(self.abs().sqrt(), self.arg() / 2.0)
+    }
+
+    pub fn exp(&self) -> Self {
+        if self.is_real() {
+            return Self::from(self.re.exp());
+        }
+        Self::from_polar(self.re.exp(), self.im)
+    }
+
+    /// Principal branch of the natural logarithm.
+    pub fn ln(&self) -> Self {
+        if self.is_real() {
+            if self.re >= 0.0 {
+                return Self::from(self.re.ln());
+            }
+            // negative reals sit on the branch cut, take the upper side even for -0i
+            return Self::new((-self.re).ln(), PI);
+        }
+        Self::new(self.abs().ln(), self.arg())
+    }
+
+    /// Principal value of `self ^ exp`.
+    pub fn pow(&self, exp: Self) -> Self {
+        if self.is_real() && exp.is_real() && (self.re >= 0.0 || exp.re.fract() == 0.0) {
+            return Self::from(self.re.powf(exp.re));
+        }
+        if exp.is_real() && exp.re.fract() == 0.0 && exp.re.abs() <= 64.0 {
+            return self.powi(exp.re as i32);
+        }
+        if *self == Self::default() {
+            if exp.re > 0.0 {
+                return Self::default();
+            }
+            return Self::from(f64::NAN);
+        }
+        (exp * self.ln()).exp()
+    }
+
+    fn powi(&self, n: i32) -> Self {
+        let mut base = *self;
+        let mut acc = Self::from(1.0);
+        let mut k = n.unsigned_abs();
+        while k > 0 {
+            if k & 1 == 1 {
+                acc = acc * base;
+            }
+            base = base * base;
+            k >>= 1;
+        }
+        if n < 0 {
+            return Self::from(1.0) / acc;
+        }
+        acc
+    }
+
+    pub fn sin(&self) -> Self {
+        if self.is_real() {
+            return Self::from(self.re.sin());
+        }
+        Self::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+
+    pub fn cos(&self) -> Self {
+        if self.is_real() {
+            return Self::from(self.re.cos());
+        }
+        Self::new(
+            self.re.cos() * self.im.cosh(),
+            -self.re.sin() * self.im.sinh(),
+        )
+    }
+
+    pub fn tan(&self) -> Self {
+        if self.is_real() {
+            return Self::from(self.re.tan());
+        }
+        self.sin() / self.cos()
+    }
+
+    // asin(z) = -i ln(iz + sqrt(1 - z^2))
+    pub fn asin(&self) -> Self {
+        if self.is_real() && self.re.abs() <= 1.0 {
+            return Self::from(self.re.asin());
+        }
+        let one = Self::from(1.0);
+        -Self::I * (Self::I * *self + (one - *self * *self).sqrt()).ln()
+    }
+
+    pub fn acos(&self) -> Self {
+        if self.is_real() && self.re.abs() <= 1.0 {
+            return Self::from(self.re.acos());
+        }
+        Self::from(PI / 2.0) - self.asin()
+    }
+
+    // atan(z) = i/2 ln((i + z) / (i - z))
+    pub fn atan(&self) -> Self {
+        if self.is_real() {
+            return Self::from(self.re.atan());
+        }
+        Self::new(0.0, 0.5) * ((Self::I + *self) / (Self::I - *self)).ln()
+    }
+
+    /// Formats the number as magnitude and angle in degrees, e.g. `2∠90°`.
+    pub fn to_polar_string(&self) -> String {
+        let deg = self.arg() * 180.0 / PI;
+        format!("{}∠{}°", self.abs(), deg)
+    }
+
+    /// Writes `re+imi`, formatting each part with `real`.
+    pub fn format_with(&self, real: impl Fn(f64) -> String) -> String {
+        let z = self.cleaned();
+        if z.im == 0.0 {
+            return real(z.re);
+        }
+
+        let im = if z.im == 1.0 {
+            "i".to_string()
+        } else if z.im == -1.0 {
+            "-i".to_string()
+        } else {
+            format!("{}i", real(z.im))
+        };
+        if z.re == 0.0 {
+            return im;
+        }
+        if im.starts_with('-') {
+            format!("{}{}", real(z.re), im)
+        } else {
+            format!("{}+{}", real(z.re), im)
+        }
+    }
+
+    // zero out parts that are only rounding noise so `e^(i*pi)` prints as `-1`
+    fn cleaned(&self) -> Self {
+        // an infinite or NaN part would make every part look like noise
+        if !self.abs().is_finite() {
+            return *self;
+        }
+        let limit = NOISE * self.abs();
+        let re = if self.re.abs() <= limit { 0.0 } else { self.re };
+        let im = if self.im.abs() <= limit { 0.0 } else { self.im };
+        Self::new(re, im)
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Self {
+        Self::new(re, 0.0)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Self) -> Self {
+        if self.is_real() && rhs.is_real() {
+            return Self::from(self.re * rhs.re);
+        }
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: Self) -> Self {
+        if rhs.is_real() {
+            return Self::new(self.re / rhs.re, self.im / rhs.re);
+        }
+        let denom = rhs.re * rhs.re + rhs.im * rhs.im;
+        Self::new(
+            (self.re * rhs.re + self.im * rhs.im) / denom,
+            (self.im * rhs.re - self.re * rhs.im) / denom,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format_with(|x| x.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Complex::new(1.0, 2.0);
+        let b = Complex::new(3.0, -1.0);
+        assert_eq!(a * b, Complex::new(5.0, 5.0));
+        assert_eq!(a + b, Complex::new(4.0, 1.0));
+        assert_eq!((a * b) / b, a);
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(Complex::from(-1.0).sqrt(), Complex::I);
+        assert_eq!((Complex::I * Complex::from(PI)).exp().to_real(), Some(-1.0));
+        assert_eq!(
+            Complex::new(1.0, 2.0).pow(Complex::from(2.0)),
+            Complex::new(-3.0, 4.0)
+        );
+
+        let root = Complex::from(-8.0).pow(Complex::from(1.0 / 3.0));
+        assert!((root.re - 1.0).abs() < 1e-12);
+        assert!((root.im - 3f64.sqrt()).abs() < 1e-12);
+
+        for z in [Complex::from(2.0), Complex::new(1.0, 1.0)] {
+            for (f, inv) in [
+                (
+                    Complex::sin as fn(&Complex) -> Complex,
+                    Complex::asin as fn(&Complex) -> Complex,
+                ),
+                (Complex::cos, Complex::acos),
+                (Complex::tan, Complex::atan),
+            ] {
+                let back = f(&inv(&z));
+                assert!((back - z).abs() < 1e-12, "{} -> {}", z, back);
+            }
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Complex::new(1.0, 2.0).to_string(), "1+2i");
+        assert_eq!(Complex::new(1.0, -2.0).to_string(), "1-2i");
+        assert_eq!(Complex::new(0.0, -1.0).to_string(), "-i");
+        assert_eq!(Complex::from(2.5).to_string(), "2.5");
+        assert_eq!(Complex::new(0.0, 2.0).to_polar_string(), "2∠90°");
+        assert_eq!(Complex::from(f64::INFINITY).to_string(), "inf");
+        assert_eq!(Complex::from(f64::NEG_INFINITY).to_string(), "-inf");
+        assert_eq!(
+            Complex::new(f64::NAN, f64::INFINITY).to_string(),
+            "NaN+infi"
+        );
+        assert_eq!(Complex::new(1.0, f64::NAN).to_string(), "1+NaNi");
+    }
+}
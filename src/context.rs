@@ -0,0 +1,85 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::number::Number;
+use crate::word::WordMode;
+
+/// The unit plain numbers are taken in by trigonometric functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AngleMode {
+    Degrees,
+    #[default]
+    Radians,
+    Gradians,
+}
+
+impl AngleMode {
+    /// Parses an angle unit suffix: `deg`, `rad` or `grad`.
+    pub fn from_suffix(name: &str) -> Option<Self> {
+        match name {
+            "deg" => Some(AngleMode::Degrees),
+            "rad" => Some(AngleMode::Radians),
+            "grad" => Some(AngleMode::Gradians),
+            _ => None,
+        }
+    }
+
+    /// The size of one unit of this mode in radians.
+    pub fn radians<N: Number>(&self) -> Result<N> {
+        let half_turn = match self {
+            AngleMode::Radians => return N::parse("1"),
+            AngleMode::Degrees => "180",
+            AngleMode::Gradians => "200",
+        };
+        N::constant("pi")?.div(&N::parse(half_turn)?)
+    }
+
+    /// The factor turning an angle in `from` units into `self` units.
+    pub fn factor_from<N: Number>(&self, from: AngleMode) -> Result<N> {
+        if *self == from {
+            return N::parse("1");
+        }
+        from.radians::<N>()?.div(&self.radians()?)
+    }
+}
+
+impl fmt::Display for AngleMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AngleMode::Degrees => "DEG",
+            AngleMode::Radians => "RAD",
+            AngleMode::Gradians => "GRAD",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Settings an expression is evaluated under.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Context {
+    pub angle: AngleMode,
+    /// Fails with `Overflow` or `DomainError` instead of returning `inf` or `NaN`.
+    pub strict: bool,
+    /// Programmer mode: integer arithmetic of a fixed width.
+    pub word: Option<WordMode>,
+    /// Seeds the random functions so every evaluation draws the same numbers.
+    pub seed: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_angle_factors() -> anyhow::Result<()> {
+        let f: f64 = AngleMode::Radians.factor_from(AngleMode::Degrees)?;
+        assert!((f - std::f64::consts::PI / 180.0).abs() < 1e-15);
+        let f: f64 = AngleMode::Degrees.factor_from(AngleMode::Gradians)?;
+        assert!((f - 0.9).abs() < 1e-15);
+        let f: f64 = AngleMode::Degrees.factor_from(AngleMode::Degrees)?;
+        assert_eq!(f, 1.0);
+        assert_eq!(AngleMode::default().to_string(), "RAD");
+        assert_eq!(AngleMode::from_suffix("grad"), Some(AngleMode::Gradians));
+        Ok(())
+    }
+}
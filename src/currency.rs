@@ -0,0 +1,147 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::errors::CaculatorError;
+use crate::number::Number;
+use crate::units::{Dimension, Quantity};
+
+/// Exchange rates loaded from a local file, quoted as units of each
+/// currency per one unit of the base currency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateTable {
+    pub date: String,
+    pub base: String,
+    // rates are kept as literals so exact backends parse them without rounding
+    rates: HashMap<String, String>,
+}
+
+impl RateTable {
+    /// Loads a `.json` or `.csv` rate table.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&text),
+            Some("csv") => Self::from_csv(&text),
+            _ => bail!(CaculatorError::InvalidRateTable(format!(
+                "unsupported file: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Parses `{"date": "2026-10-01", "base": "USD", "rates": {"EUR": 0.92}}`.
+    pub fn from_json(text: &str) -> Result<Self> {
+        let json: serde_json::Value = serde_json::from_str(text)?;
+        let field = |name: &str| match json.get(name).and_then(|v| v.as_str()) {
+            Some(v) => Ok(v.to_string()),
+            None => Err(CaculatorError::InvalidRateTable(format!(
+                "missing {}",
+                name
+            ))),
+        };
+        let (date, base) = (field("date")?, field("base")?);
+
+        let Some(entries) = json.get("rates").and_then(|r| r.as_object()) else {
+            bail!(CaculatorError::InvalidRateTable(
+                "missing rates".to_string()
+            ));
+        };
+        let mut rates = HashMap::new();
+        for (code, rate) in entries {
+            let Some(rate) = rate.as_number() else {
+                bail!(CaculatorError::InvalidRateTable(code.clone()));
+            };
+            rates.insert(code.clone(), rate.to_string());
+        }
+
+        Self::new(date, base, rates)
+    }
+
+    /// Parses rows of `date,base,currency,rate` after a header line.
+    pub fn from_csv(text: &str) -> Result<Self> {
+        let mut table: Option<(String, String)> = None;
+        let mut rates = HashMap::new();
+
+        for line in text.lines().skip(1).filter(|l| !l.trim().is_empty()) {
+            let cols: Vec<&str> = line.split(',').map(|c| c.trim()).collect();
+            let [date, base, code, rate] = cols[..] else {
+                bail!(CaculatorError::InvalidRateTable(line.to_string()));
+            };
+            match &table {
+                Some((d, b)) if d != date || b != base => {
+                    bail!(CaculatorError::InvalidRateTable(line.to_string()));
+                }
+                Some(_) => {}
+                None => table = Some((date.to_string(), base.to_string())),
+            }
+            rates.insert(code.to_string(), rate.to_string());
+        }
+
+        let Some((date, base)) = table else {
+            bail!(CaculatorError::InvalidRateTable("no rates".to_string()));
+        };
+        Self::new(date, base, rates)
+    }
+
+    fn new(date: String, base: String, mut rates: HashMap<String, String>) -> Result<Self> {
+        for (code, rate) in &rates {
+            if rate.parse::<f64>().map_or(true, |r| r <= 0.0) {
+                bail!(CaculatorError::InvalidRateTable(format!(
+                    "{}: {}",
+                    code, rate
+                )));
+            }
+        }
+        rates.insert(base.clone(), "1".to_string());
+        Ok(Self { date, base, rates })
+    }
+
+    /// One unit of `code` as an amount of money in the base currency.
+    pub fn currency<N: Number>(&self, code: &str) -> Result<Quantity<N>> {
+        let Some(rate) = self.rates.get(code) else {
+            bail!(CaculatorError::MissingRate(code.to_string()));
+        };
+        let factor = N::parse("1")?.div(&N::parse(rate)?)?;
+        Ok(Quantity {
+            value: factor.clone(),
+            dim: Dimension::MONEY,
+            unit: Some((code.to_string(), factor)),
+        })
+    }
+}
+
+/// Whether `name` is written like an ISO 4217 code, e.g. `USD`.
+pub fn is_currency_code(name: &str) -> bool {
+    name.len() == 3 && name.chars().all(|c| c.is_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rational::Rational;
+
+    #[test]
+    fn test_parse_tables() -> anyhow::Result<()> {
+        let json = r#"{"date": "2026-10-01", "base": "USD", "rates": {"EUR": 0.8, "JPY": 150}}"#;
+        let csv = "date,base,currency,rate\n2026-10-01,USD,EUR,0.8\n2026-10-01,USD,JPY,150\n";
+
+        for table in [RateTable::from_json(json)?, RateTable::from_csv(csv)?] {
+            assert_eq!(table.date, "2026-10-01");
+            assert_eq!(table.base, "USD");
+            let eur = table.currency::<Rational>("EUR")?;
+            assert_eq!(eur.value, Rational::new(5, 4)?);
+            assert_eq!(
+                table.currency::<Rational>("USD")?.value,
+                Rational::from_int(1)
+            );
+            assert!(table.currency::<Rational>("GBP").is_err());
+        }
+
+        assert!(RateTable::from_csv("date,base,currency,rate\n2026-10-01,USD,EUR\n").is_err());
+        assert!(RateTable::from_json(r#"{"base": "USD", "rates": {}}"#).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::{bail, Result};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::errors::CaculatorError;
+
+const SECS_PER_DAY: i64 = 86400;
+
+/// A point in time in UTC, as seconds since 1970-01-01.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    secs: i64,
+}
+
+impl Date {
+    pub fn from_ymd_hms(y: i64, m: u32, d: u32, hh: u32, mm: u32, ss: u32) -> Result<Self> {
+        let valid = (1..=12).contains(&m)
+            && d >= 1
+            && d <= days_in_month(y, m)
+            && hh < 24
+            && mm < 60
+            && ss < 60;
+        if !valid {
+            bail!(CaculatorError::InvalidDate(format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                y, m, d, hh, mm, ss
+            )));
+        }
+        let secs = days_from_civil(y, m, d) * SECS_PER_DAY
+            + (hh as i64) * 3600
+            + (mm as i64) * 60
+            + ss as i64;
+        Ok(Self { secs })
+    }
+
+    pub fn now() -> Self {
+        let secs = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(d) => d.as_secs() as i64,
+            Err(e) => -(e.duration().as_secs() as i64),
+        };
+        Self { secs }
+    }
+
+    /// Midnight of the current day.
+    pub fn today() -> Self {
+        let now = Self::now();
+        Self {
+            secs: now.secs.div_euclid(SECS_PER_DAY) * SECS_PER_DAY,
+        }
+    }
+
+    /// Parses `2026-12-25`, `2026-12-25T14:30` or `2026-12-25 14:30:15`.
+    pub fn parse(literal: &str) -> Result<Self> {
+        let invalid = || CaculatorError::InvalidDate(literal.to_string());
+        let (date, time) = match literal.split_once(['T', ' ']) {
+            Some((date, time)) => (date, time),
+            None => (literal, "00:00"),
+        };
+
+        let ymd: Vec<&str> = date.split('-').collect();
+        let hms: Vec<&str> = time.split(':').collect();
+        if ymd.len() != 3 || !(2..=3).contains(&hms.len()) {
+            bail!(invalid());
+        }
+        let num = |s: &str| s.parse::<u32>().map_err(|_| invalid());
+        Self::from_ymd_hms(
+            num(ymd[0])? as i64,
+            num(ymd[1])?,
+            num(ymd[2])?,
+            num(hms[0])?,
+            num(hms[1])?,
+            match hms.get(2) {
+                Some(s) => num(s)?,
+                None => 0,
+            },
+        )
+    }
+
+    /// Moves the date by a number of seconds, rounded to whole seconds.
+    pub fn add_secs(&self, secs: f64) -> Result<Self> {
+        let secs = secs.round();
+        if !secs.is_finite() || secs.abs() > 1e15 {
+            bail!(CaculatorError::Overflow {
+                op: format!("{} + {}s", self, secs),
+                span: None,
+            });
+        }
+        Ok(Self {
+            secs: self.secs + secs as i64,
+        })
+    }
+
+    /// Seconds from `other` to `self`.
+    pub fn secs_since(&self, other: &Self) -> i64 {
+        self.secs - other.secs
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = self.secs.div_euclid(SECS_PER_DAY);
+        let rest = self.secs.rem_euclid(SECS_PER_DAY);
+        let (y, m, d) = civil_from_days(days);
+        write!(f, "{:04}-{:02}-{:02}", y, m, d)?;
+        if rest != 0 {
+            write!(
+                f,
+                " {:02}:{:02}:{:02}",
+                rest / 3600,
+                rest % 3600 / 60,
+                rest % 60
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a number of seconds in human units, e.g. `2 days 3h 15min`.
+pub fn format_duration(secs: f64) -> String {
+    if !secs.is_finite() {
+        return format!("{}s", secs);
+    }
+    let sign = if secs < 0.0 { "-" } else { "" };
+    // keep millisecond precision so 0.1 + 0.2 seconds prints as 0.3s
+    let total = (secs.abs() * 1000.0).round() / 1000.0;
+
+    let days = (total / SECS_PER_DAY as f64).floor();
+    let rest = total - days * SECS_PER_DAY as f64;
+    let hours = (rest / 3600.0).floor();
+    let rest = rest - hours * 3600.0;
+    let mins = (rest / 60.0).floor();
+    let secs = ((rest - mins * 60.0) * 1000.0).round() / 1000.0;
+
+    let mut parts = Vec::new();
+    match days {
+        1.0 => parts.push("1 day".to_string()),
+        d if d > 0.0 => parts.push(format!("{} days", d)),
+        _ => {}
+    }
+    if hours > 0.0 {
+        parts.push(format!("{}h", hours));
+    }
+    if mins > 0.0 {
+        parts.push(format!("{}min", mins));
+    }
+    if secs > 0.0 || parts.is_empty() {
+        parts.push(format!("{}s", secs));
+    }
+    format!("{}{}", sign, parts.join(" "))
+}
+
+fn is_leap(y: i64) -> bool {
+    (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
+}
+
+fn days_in_month(y: i64, m: u32) -> u32 {
+    match m {
+        2 if is_leap(y) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = m as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() -> anyhow::Result<()> {
+        assert_eq!(Date::parse("1970-01-01")?.secs, 0);
+        assert_eq!(Date::parse("2024-02-29")?.to_string(), "2024-02-29");
+        assert_eq!(
+            Date::parse("2026-12-25T14:30")?.to_string(),
+            "2026-12-25 14:30:00"
+        );
+        assert_eq!(Date::parse("1969-12-31 23:59:59")?.secs, -1);
+        assert!(Date::parse("2026-02-29").is_err());
+        assert!(Date::parse("2026-13-01").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_arithmetic() -> anyhow::Result<()> {
+        let christmas = Date::parse("2026-12-25")?;
+        let newyear = Date::parse("2027-01-01")?;
+        assert_eq!(newyear.secs_since(&christmas), 7 * SECS_PER_DAY);
+        assert_eq!(
+            christmas.add_secs(90.0 * 86400.0)?.to_string(),
+            "2027-03-25"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(8100.0), "2h 15min");
+        assert_eq!(format_duration(86400.0 * 67.0), "67 days");
+        assert_eq!(format_duration(90061.5), "1 day 1h 1min 1.5s");
+        assert_eq!(format_duration(-60.0), "-1min");
+        assert_eq!(format_duration(0.0), "0s");
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -12,4 +13,99 @@ pub enum CaculatorError {
     DivideByZero,
     #[error("unbalanced parenthesis")]
     UnBalancedParenthesis,
+    #[error("unknown identifier: {0}")]
+    UnknownIdentifier(String),
+    #[error("unknown function: {0}")]
+    UnknownFunction(String),
+    #[error("function: {0} need {1} arguments")]
+    ArgumentCount(String, usize),
+    #[error("result is not a real number: {0}")]
+    NotReal(String),
+    #[error("number overflow in: {op}{}", at(.span))]
+    Overflow { op: String, span: Option<Span> },
+    #[error("no exact result for: {0}")]
+    Inexact(String),
+    #[error("dimension mismatch: {0} and {1}")]
+    DimensionMismatch(String, String),
+    #[error("no exchange rate for: {0}")]
+    MissingRate(String),
+    #[error("invalid rate table: {0}")]
+    InvalidRateTable(String),
+    #[error("invalid date: {0}")]
+    InvalidDate(String),
+    #[error("radix must be between 2 and 36: {0}")]
+    InvalidRadix(u32),
+    #[error("{kind}: {op}{}", at(.span))]
+    DomainError {
+        kind: Domain,
+        op: String,
+        span: Option<Span>,
+    },
+    #[error("intervals cannot have units: {0}")]
+    IntervalUnit(String),
+    #[error("shape mismatch: {0} and {1}")]
+    ShapeMismatch(String, String),
+    #[error("matrix is singular")]
+    SingularMatrix,
+    #[error("cannot differentiate: {0}")]
+    NotDifferentiable(String),
+    #[error("no root found for: {0}")]
+    NoRoot(String),
+    #[error("no convergence for: {0}")]
+    NoConvergence(String),
+    #[error("more than {0} iterations")]
+    IterationLimit(usize),
+    #[error("not a linear equation: {0}")]
+    NotLinear(String),
+    #[error("system has infinitely many solutions")]
+    Underdetermined,
+    #[error("system has no solution")]
+    Inconsistent,
+    #[error("not an integer: {0}")]
+    NotInteger(String),
+}
+
+/// Why an operation has no result for its operands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Domain {
+    /// The result is `NaN`, as for `sqrt(-1)` on a real backend.
+    NotANumber,
+    /// A function of integers got a fraction.
+    NotInteger,
+    /// An integer lies outside the function's domain, like `factor(1)`.
+    OutOfRange,
+    /// `modinv` and `powmod` need a number coprime to the modulus.
+    NoInverse,
+}
+
+impl fmt::Display for Domain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Domain::NotANumber => "result is not a number",
+            Domain::NotInteger => "not an integer",
+            Domain::OutOfRange => "argument out of range",
+            Domain::NoInverse => "no modular inverse",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// A range of characters in the expression, end exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+fn at(span: &Option<Span>) -> String {
+    match span {
+        Some(span) => format!(" at {}", span),
+        None => String::new(),
+    }
 }
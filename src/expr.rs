@@ -0,0 +1,544 @@
+use anyhow::{bail, Result};
+use std::fmt;
+
+use crate::errors::CaculatorError;
+use crate::format::Format;
+use crate::number::Number;
+use crate::value::{Value, LIST};
+
+/// An expression in one or more free variables, such as the `x^2 + 1`
+/// of `diff(x^2 + 1, x)`.
+///
+/// Nodes are built through [`Expr::op`] and friends, which fold constants
+/// and drop identities like `x * 1` as they go.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr<N> {
+    Num(N),
+    Var(String),
+    Neg(Box<Expr<N>>),
+    // one of `+ - * / ^`
+    Op(char, Box<Expr<N>>, Box<Expr<N>>),
+    Call(String, Vec<Expr<N>>),
+}
+
+impl<N: Number> Expr<N> {
+    pub fn num(literal: &str) -> Result<Self> {
+        Ok(Expr::Num(N::parse(literal)?))
+    }
+
+    fn is_num(&self, literal: &str) -> bool {
+        matches!(self, Expr::Num(n) if N::parse(literal).is_ok_and(|m| *n == m))
+    }
+
+    /// Whether `var` occurs in the expression.
+    pub fn contains(&self, var: &str) -> bool {
+        match self {
+            Expr::Num(_) => false,
+            Expr::Var(name) => name == var,
+            Expr::Neg(a) => a.contains(var),
+            Expr::Op(_, a, b) => a.contains(var) || b.contains(var),
+            Expr::Call(_, args) => args.iter().any(|a| a.contains(var)),
+        }
+    }
+
+    /// `a op b`, folded where the result is obvious.
+    pub fn op(op: char, a: Self, b: Self) -> Result<Self> {
+        if let (Expr::Num(x), Expr::Num(y)) = (&a, &b) {
+            let folded = match op {
+                '+' => x.add(y),
+                '-' => x.sub(y),
+                '*' => x.mul(y),
+                '/' if !y.is_zero() => x.div(y),
+                '^' => x.pow(y),
+                _ => bail!(CaculatorError::UnsupportedOperator(op)),
+            };
+            // keep what the backend cannot do exactly, e.g. `2^(1/2)` over rationals
+            if let Ok(n) = folded {
+                return Ok(Expr::Num(n));
+            }
+        }
+        let (zero, one) = ("0", "1");
+        let e = match op {
+            '+' if a.is_num(zero) => b,
+            '+' | '-' if b.is_num(zero) => a,
+            '-' if a.is_num(zero) => Self::negate(b)?,
+            '-' if a == b => Self::num(zero)?,
+            '*' if a.is_num(zero) || b.is_num(zero) => Self::num(zero)?,
+            '*' if a.is_num(one) => b,
+            '*' if b.is_num(one) => a,
+            // constants go first and gather: `2*(3*x)` is `6*x`
+            '*' if matches!(b, Expr::Num(_)) => Self::op('*', b, a)?,
+            '*' => match (a, b) {
+                (Expr::Num(k), Expr::Op('*', c, rest)) if matches!(*c, Expr::Num(_)) => {
+                    Self::op('*', Self::op('*', Expr::Num(k), *c)?, *rest)?
+                }
+                (a, b) => Expr::Op('*', Box::new(a), Box::new(b)),
+            },
+            '/' if b.is_num(one) => a,
+            '/' if a.is_num(zero) => Self::num(zero)?,
+            '/' if a == b => Self::num(one)?,
+            '^' if b.is_num(zero) || a.is_num(one) => Self::num(one)?,
+            '^' if b.is_num(one) => a,
+            _ => Expr::Op(op, Box::new(a), Box::new(b)),
+        };
+        Ok(e)
+    }
+
+    pub fn negate(a: Self) -> Result<Self> {
+        Ok(match a {
+            Expr::Num(n) => Expr::Num(n.neg()?),
+            Expr::Neg(a) => *a,
+            a => Expr::Neg(Box::new(a)),
+        })
+    }
+
+    pub fn call(name: &str, args: Vec<Self>) -> Self {
+        Expr::Call(name.to_string(), args)
+    }
+
+    /// Evaluates with `var` bound to `value`; other variables stay symbolic.
+    pub fn eval(&self, var: &str, value: &Value<N>) -> Result<Value<N>> {
+        match self {
+            Expr::Num(n) => Ok(Value::Num(n.clone())),
+            Expr::Var(name) if name == var => Ok(value.clone()),
+            Expr::Var(_) => Ok(Value::Expr(self.clone())),
+            Expr::Neg(a) => a.eval(var, value)?.neg(),
+            Expr::Op(op, a, b) => {
+                let (a, b) = (a.eval(var, value)?, b.eval(var, value)?);
+                match op {
+                    '+' => a.add(&b),
+                    '-' => a.sub(&b),
+                    '*' => a.mul(&b),
+                    '/' => a.div(&b),
+                    '^' => a.pow(&b),
+                    _ => bail!(CaculatorError::UnsupportedOperator(*op)),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|a| a.eval(var, value))
+                    .collect::<Result<Vec<_>>>()?;
+                Value::call(name, &args)
+            }
+        }
+    }
+
+    /// The derivative with respect to `var`.
+    pub fn diff(&self, var: &str) -> Result<Self> {
+        let num = Self::num;
+        let op = Self::op;
+        let e = match self {
+            Expr::Num(_) => num("0")?,
+            Expr::Var(name) => num(if name == var { "1" } else { "0" })?,
+            Expr::Neg(a) => Self::negate(a.diff(var)?)?,
+            Expr::Op(o, a, b) => {
+                let (a, b) = (a.as_ref().clone(), b.as_ref().clone());
+                let (da, db) = (a.diff(var)?, b.diff(var)?);
+                match o {
+                    '+' | '-' => op(*o, da, db)?,
+                    '*' => op('+', op('*', da, b)?, op('*', a, db)?)?,
+                    '/' => {
+                        let top = op('-', op('*', da, b.clone())?, op('*', a, db)?)?;
+                        op('/', top, op('^', b, num("2")?)?)?
+                    }
+                    // power rule for constant exponents
+                    '^' if !b.contains(var) => {
+                        let power = op('^', a, op('-', b.clone(), num("1")?)?)?;
+                        op('*', op('*', b, power)?, da)?
+                    }
+                    // d(a^b) = a^b * (b' ln a + b a' / a)
+                    '^' => {
+                        let ln = Self::call("ln", vec![a.clone()]);
+                        let inner = op(
+                            '+',
+                            op('*', db, ln)?,
+                            op('/', op('*', b.clone(), da)?, a.clone())?,
+                        )?;
+                        op('*', op('^', a, b)?, inner)?
+                    }
+                    _ => bail!(CaculatorError::UnsupportedOperator(*o)),
+                }
+            }
+            Expr::Call(name, args) => {
+                let [u] = args.as_slice() else {
+                    bail!(CaculatorError::NotDifferentiable(self.to_string()));
+                };
+                let du = u.diff(var)?;
+                let call = |f: &str, x: Self| Self::call(f, vec![x]);
+                let u = u.clone();
+                // the derivative of the outer function at `u`
+                let outer = match name.as_str() {
+                    "sin" => call("cos", u),
+                    "cos" => Self::negate(call("sin", u))?,
+                    "tan" => op('/', num("1")?, op('^', call("cos", u), num("2")?)?)?,
+                    "exp" => call("exp", u),
+                    "ln" => op('/', num("1")?, u)?,
+                    "log" => op('/', num("1")?, op('*', u, call("ln", num("10")?))?)?,
+                    "sqrt" => op('/', num("1")?, op('*', num("2")?, call("sqrt", u))?)?,
+                    "asin" | "acos" => {
+                        let root = call("sqrt", op('-', num("1")?, op('^', u, num("2")?)?)?);
+                        let d = op('/', num("1")?, root)?;
+                        if name == "acos" {
+                            Self::negate(d)?
+                        } else {
+                            d
+                        }
+                    }
+                    "atan" => op('/', num("1")?, op('+', num("1")?, op('^', u, num("2")?)?)?)?,
+                    "abs" => op('/', u.clone(), call("abs", u))?,
+                    _ => bail!(CaculatorError::NotDifferentiable(self.to_string())),
+                };
+                op('*', outer, du)?
+            }
+        };
+        Ok(e)
+    }
+
+    /// An equivalent simpler expression: constants folded, like terms and
+    /// equal factors collected, so `2*x + 3*x - x` becomes `4*x`.
+    pub fn simplify(&self) -> Result<Self> {
+        match self {
+            Expr::Num(_) | Expr::Var(_) => Ok(self.clone()),
+            Expr::Neg(a) => Self::sum(&Self::negate(a.simplify()?)?),
+            Expr::Op(op, a, b) => {
+                let e = Self::op(*op, a.simplify()?, b.simplify()?)?;
+                match e {
+                    Expr::Op('+' | '-', ..) => Self::sum(&e),
+                    Expr::Op('*' | '/', ..) => Self::product(&e),
+                    Expr::Op('^', base, q) => match (*base, *q) {
+                        (Expr::Op('^', base, p), Expr::Num(q)) if matches!(*p, Expr::Num(_)) => {
+                            let Expr::Num(p) = *p else { unreachable!() };
+                            Self::power_of_power(*base, p, q)
+                        }
+                        (base, q) => Ok(Expr::Op('^', Box::new(base), Box::new(q))),
+                    },
+                    e => Ok(e),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(Self::simplify)
+                    .collect::<Result<Vec<_>>>()?;
+                let nums: Option<Vec<N>> = args
+                    .iter()
+                    .map(|a| match a {
+                        Expr::Num(n) => Some(n.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                // functions of constants are constants, where the backend knows them
+                match nums.map(|nums| N::call(name, &nums)) {
+                    Some(Ok(n)) => Ok(Expr::Num(n)),
+                    _ => Ok(Self::call(name, args)),
+                }
+            }
+        }
+    }
+
+    // `(x^p)^q` as `x^(p*q)` where that keeps the sign of `x^p`: `(x^2)^3` is
+    // `x^6`, but `(x^2)^0.5` is `abs(x)`
+    fn power_of_power(base: Self, p: N, q: N) -> Result<Self> {
+        let pq = p.mul(&q)?;
+        let parity = |n: &N| {
+            n.to_f64()
+                .filter(|x| x.fract() == 0.0 && x.abs() < 2f64.powi(53))
+                .map(|x| x % 2.0 == 0.0)
+        };
+        match (parity(&p), parity(&q), parity(&pq)) {
+            (_, Some(_), _) | (Some(false), ..) | (.., Some(true)) => {
+                Self::op('^', base, Expr::Num(pq))
+            }
+            (Some(true), _, Some(false)) => {
+                Self::op('^', Self::call("abs", vec![base]), Expr::Num(pq))
+            }
+            _ => Ok(Expr::Op(
+                '^',
+                Box::new(Expr::Op('^', Box::new(base), Box::new(Expr::Num(p)))),
+                Box::new(Expr::Num(q)),
+            )),
+        }
+    }
+
+    // collects the terms of a sum by their non-constant part: `2*x + 3*x` is `5*x`
+    fn sum(e: &Self) -> Result<Self> {
+        let mut terms: Vec<(Option<Self>, N)> = Vec::new();
+        Self::terms(e, false, &mut terms)?;
+
+        // the constant goes last: `2*x + 1`
+        terms.sort_by_key(|(term, _)| term.is_none());
+        let mut acc: Option<Self> = None;
+        for (term, k) in terms {
+            if k.is_zero() {
+                continue;
+            }
+            let negative = acc.is_some() && k.to_f64().is_some_and(|k| k < 0.0);
+            let k = if negative { k.neg()? } else { k };
+            let term = match term {
+                Some(term) => Self::scale(k, term)?,
+                None => Expr::Num(k),
+            };
+            acc = Some(match acc {
+                None => term,
+                Some(acc) => Self::op(if negative { '-' } else { '+' }, acc, term)?,
+            });
+        }
+        Ok(acc.unwrap_or(Self::num("0")?))
+    }
+
+    fn terms(e: &Self, negative: bool, out: &mut Vec<(Option<Self>, N)>) -> Result<()> {
+        match e {
+            Expr::Op('+', a, b) => {
+                Self::terms(a, negative, out)?;
+                Self::terms(b, negative, out)
+            }
+            Expr::Op('-', a, b) => {
+                Self::terms(a, negative, out)?;
+                Self::terms(b, !negative, out)
+            }
+            Expr::Neg(a) => Self::terms(a, !negative, out),
+            e => {
+                let (k, term) = match e {
+                    Expr::Num(n) => (n.clone(), None),
+                    Expr::Op('*', k, term) if matches!(**k, Expr::Num(_)) => {
+                        let Expr::Num(k) = k.as_ref() else {
+                            unreachable!()
+                        };
+                        (k.clone(), Some(term.as_ref().clone()))
+                    }
+                    e => (N::parse("1")?, Some(e.clone())),
+                };
+                let k = if negative { k.neg()? } else { k };
+                match out.iter_mut().find(|(t, _)| *t == term) {
+                    Some((_, sum)) => *sum = sum.add(&k)?,
+                    None => out.push((term, k)),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // `k*e`, written `-e` for a coefficient of -1
+    fn scale(k: N, e: Self) -> Result<Self> {
+        if k.neg()?.sub(&N::parse("1")?)?.is_zero() {
+            return Self::negate(e);
+        }
+        Self::op('*', Expr::Num(k), e)
+    }
+
+    // collects equal factors of a product into powers: `x*3*x/x^3` is `3/x`
+    fn product(e: &Self) -> Result<Self> {
+        let mut k = N::parse("1")?;
+        let mut factors: Vec<(Self, N)> = Vec::new();
+        if !Self::factors(e, false, &mut k, &mut factors)? {
+            return Ok(e.clone());
+        }
+        factors.retain(|(_, p)| !p.is_zero());
+        factors.sort_by_key(|(base, _)| base.to_string());
+
+        let (mut top, mut bottom) = (Self::num("1")?, Self::num("1")?);
+        for (base, p) in factors {
+            if p.to_f64().is_some_and(|p| p < 0.0) {
+                bottom = Self::op('*', bottom, Self::op('^', base, Expr::Num(p.neg()?))?)?;
+            } else {
+                top = Self::op('*', top, Self::op('^', base, Expr::Num(p))?)?;
+            }
+        }
+        if top.is_num("1") && !bottom.is_num("1") {
+            return Self::op('/', Expr::Num(k), bottom);
+        }
+        Self::scale(k, Self::op('/', top, bottom)?)
+    }
+
+    // false if the product divides by zero and is better left alone
+    fn factors(e: &Self, inverse: bool, k: &mut N, out: &mut Vec<(Self, N)>) -> Result<bool> {
+        let (base, p) = match e {
+            Expr::Op('*', a, b) => {
+                return Ok(Self::factors(a, inverse, k, out)? && Self::factors(b, inverse, k, out)?)
+            }
+            Expr::Op('/', a, b) => {
+                return Ok(Self::factors(a, inverse, k, out)? && Self::factors(b, !inverse, k, out)?)
+            }
+            Expr::Neg(a) => {
+                *k = k.neg()?;
+                return Self::factors(a, inverse, k, out);
+            }
+            Expr::Num(n) if inverse && n.is_zero() => return Ok(false),
+            Expr::Num(n) => {
+                *k = if inverse { k.div(n)? } else { k.mul(n)? };
+                return Ok(true);
+            }
+            Expr::Op('^', base, p) if matches!(**p, Expr::Num(_)) => {
+                let Expr::Num(p) = p.as_ref() else {
+                    unreachable!()
+                };
+                (base.as_ref().clone(), p.clone())
+            }
+            e => (e.clone(), N::parse("1")?),
+        };
+        let p = if inverse { p.neg()? } else { p };
+        match out.iter_mut().find(|(b, _)| *b == base) {
+            Some((_, sum)) => *sum = sum.add(&p)?,
+            None => out.push((base, p)),
+        }
+        Ok(true)
+    }
+
+    // how tightly the node binds when written out, to place parentheses
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Op('+' | '-', ..) => 1,
+            Expr::Op('*' | '/', ..) => 2,
+            Expr::Neg(_) => 3,
+            Expr::Op(..) => 4,
+            Expr::Num(n) => match n.to_f64() {
+                Some(x) if x < 0.0 => 3,
+                Some(_) => 5,
+                // a complex number such as `1+2i`
+                None => 1,
+            },
+            Expr::Var(_) | Expr::Call(..) => 5,
+        }
+    }
+
+    /// Writes the expression with its numbers in the given notation: `2*x + 1`.
+    pub fn format(&self, format: &Format) -> String {
+        let wrap = |e: &Self, parens: bool| {
+            let s = e.format(format);
+            if parens {
+                format!("({})", s)
+            } else {
+                s
+            }
+        };
+        match self {
+            Expr::Num(n) => n.format(format),
+            Expr::Var(name) => name.clone(),
+            Expr::Neg(a) => format!("-{}", wrap(a, a.precedence() < 3)),
+            Expr::Op(op, a, b) => {
+                let p = self.precedence();
+                // `^` groups to the right, the others to the left
+                let (left, right) = match op {
+                    '^' => (a.precedence() <= p, b.precedence() < p),
+                    '-' | '/' => (a.precedence() < p, b.precedence() <= p),
+                    _ => (a.precedence() < p, b.precedence() < p),
+                };
+                let symbol = match op {
+                    '+' | '-' => format!(" {} ", op),
+                    op => op.to_string(),
+                };
+                format!("{}{}{}", wrap(a, left), symbol, wrap(b, right))
+            }
+            Expr::Call(name, args) => {
+                let args: Vec<String> = args.iter().map(|a| a.format(format)).collect();
+                if name == LIST {
+                    return format!("[{}]", args.join(", "));
+                }
+                format!("{}({})", name, args.join(", "))
+            }
+        }
+    }
+}
+
+impl<N: Number> fmt::Display for Expr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(&Format::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn x() -> Expr<f64> {
+        Expr::Var("x".to_string())
+    }
+
+    fn n(x: f64) -> Expr<f64> {
+        Expr::Num(x)
+    }
+
+    #[test]
+    fn test_folding() -> anyhow::Result<()> {
+        assert_eq!(Expr::op('+', n(1.0), n(2.0))?, n(3.0));
+        assert_eq!(Expr::op('*', x(), n(1.0))?, x());
+        assert_eq!(Expr::op('*', x(), n(0.0))?, n(0.0));
+        assert_eq!(Expr::op('-', x(), x())?, n(0.0));
+        assert_eq!(Expr::op('*', x(), n(2.0))?.to_string(), "2*x");
+        assert_eq!(Expr::negate(Expr::negate(x())?)?, x());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format() -> anyhow::Result<()> {
+        let sum = Expr::op('+', x(), n(1.0))?;
+        let t_cases = vec![
+            (Expr::op('*', n(2.0), sum.clone())?, "2*(x + 1)"),
+            (Expr::op('-', x(), sum.clone())?, "x - (x + 1)"),
+            (Expr::op('^', sum.clone(), n(2.0))?, "(x + 1)^2"),
+            (Expr::op('^', x(), Expr::op('^', x(), n(2.0))?)?, "x^x^2"),
+            (Expr::negate(Expr::op('^', x(), n(2.0))?)?, "-x^2"),
+            (
+                Expr::op('/', n(1.0), Expr::op('*', n(2.0), x())?)?,
+                "1/(2*x)",
+            ),
+            (Expr::call("sin", vec![sum]), "sin(x + 1)"),
+        ];
+        for tc in t_cases {
+            assert_eq!(tc.0.to_string(), tc.1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff() -> anyhow::Result<()> {
+        let square = Expr::op('^', x(), n(2.0))?;
+        assert_eq!(square.diff("x")?.to_string(), "2*x");
+        assert_eq!(square.diff("y")?, n(0.0));
+        let sin = Expr::call("sin", vec![square.clone()]);
+        assert_eq!(sin.diff("x")?.to_string(), "cos(x^2)*2*x");
+        assert!(Expr::call("floor", vec![x()]).diff("x").is_err());
+
+        let Value::Num(r) = sin.diff("x")?.eval("x", &Value::Num(0.0))? else {
+            panic!("expected a number");
+        };
+        assert_eq!(r, 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_simplify() -> anyhow::Result<()> {
+        let y = || Expr::Var("y".to_string());
+        let term = |k: f64, e: Expr<f64>| Expr::op('*', n(k), e).unwrap();
+        let t_cases = vec![
+            // 2*x + 3*x - x
+            (
+                Expr::op('-', Expr::op('+', term(2.0, x()), term(3.0, x()))?, x())?,
+                "4*x",
+            ),
+            (Expr::op('-', x(), x())?, "0"),
+            (
+                Expr::op(
+                    '+',
+                    Expr::op('+', x(), n(1.0))?,
+                    Expr::op('-', n(2.0), x())?,
+                )?,
+                "3",
+            ),
+            (Expr::op('*', x(), x())?, "x^2"),
+            (Expr::op('/', Expr::op('*', x(), y())?, x())?, "y"),
+            (Expr::op('*', y(), x())?, "x*y"),
+            (Expr::op('^', Expr::op('^', x(), n(2.0))?, n(3.0))?, "x^6"),
+            (Expr::op('/', n(3.0), Expr::op('^', x(), n(2.0))?)?, "3/x^2"),
+            (Expr::op('-', n(1.0), term(2.0, x()))?, "-2*x + 1"),
+            (Expr::negate(Expr::op('-', x(), n(1.0))?)?, "-x + 1"),
+            (Expr::call("sqrt", vec![n(4.0)]), "2"),
+            (Expr::op('/', x(), n(0.0))?, "x/0"),
+        ];
+        for tc in t_cases {
+            assert_eq!(tc.0.simplify()?.to_string(), tc.1, "{}", tc.0);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+// Time value of money with the sign convention of spreadsheets: money paid
+// out is negative, money received positive, so a loan's present value and
+// its payments have opposite signs. `due` marks payments at the start of
+// each period instead of the end.
+
+use std::ops::RangeInclusive;
+
+use crate::solve;
+
+/// How many arguments a finance function takes, `None` for other names.
+pub fn arity(name: &str) -> Option<RangeInclusive<usize>> {
+    match name {
+        "pmt" | "pv" | "fv" | "nper" => Some(3..=5),
+        "rate" => Some(3..=6),
+        "amort" => Some(3..=3),
+        "irr" => Some(1..=usize::MAX),
+        "npv" => Some(2..=usize::MAX),
+        "xnpv" => Some(3..=usize::MAX),
+        _ => None,
+    }
+}
+
+// `(1 + rate)^nper` and the payment factor, the growth of one per period paid
+fn growth(rate: f64, nper: f64, due: bool) -> (f64, f64) {
+    let g = (1.0 + rate).powf(nper);
+    let annuity = if rate == 0.0 {
+        nper
+    } else {
+        (1.0 + rate * due as u8 as f64) * (g - 1.0) / rate
+    };
+    (g, annuity)
+}
+
+/// The payment per period that pays off `pv` down to `fv`.
+pub fn pmt(rate: f64, nper: f64, pv: f64, fv: f64, due: bool) -> f64 {
+    let (g, annuity) = growth(rate, nper, due);
+    -(pv * g + fv) / annuity
+}
+
+pub fn pv(rate: f64, nper: f64, pmt: f64, fv: f64, due: bool) -> f64 {
+    let (g, annuity) = growth(rate, nper, due);
+    -(pmt * annuity + fv) / g
+}
+
+pub fn fv(rate: f64, nper: f64, pmt: f64, pv: f64, due: bool) -> f64 {
+    let (g, annuity) = growth(rate, nper, due);
+    -(pv * g + pmt * annuity)
+}
+
+/// The number of periods, `None` if the payments never reach `fv`.
+pub fn nper(rate: f64, pmt: f64, pv: f64, fv: f64, due: bool) -> Option<f64> {
+    let n = if rate == 0.0 {
+        -(pv + fv) / pmt
+    } else {
+        let p = pmt * (1.0 + rate * due as u8 as f64);
+        ((p - fv * rate) / (p + pv * rate)).ln() / (1.0 + rate).ln()
+    };
+    n.is_finite().then_some(n)
+}
+
+/// The interest rate per period, found from `guess`.
+pub fn rate(nper: f64, pmt: f64, pv: f64, fv: f64, due: bool, guess: f64) -> Option<f64> {
+    solve::root(|r| self::fv(r, nper, pmt, pv, due) - fv, guess)
+}
+
+/// The value now of `values` paid at the end of the next periods.
+pub fn npv(rate: f64, values: &[f64]) -> f64 {
+    values
+        .iter()
+        .zip(1..)
+        .map(|(v, i)| v / (1.0 + rate).powi(i))
+        .sum()
+}
+
+/// The rate at which `values`, the first paid now, are worth nothing.
+pub fn irr(values: &[f64], guess: f64) -> Option<f64> {
+    let (first, rest) = values.split_first()?;
+    // without money going both ways there is no such rate
+    if values.iter().all(|&v| v >= 0.0) || values.iter().all(|&v| v <= 0.0) {
+        return None;
+    }
+    solve::root(|r| first + npv(r, rest), guess)
+}
+
+/// `npv` for payments on `days`, counted from the first with 365 days a year.
+pub fn xnpv(rate: f64, values: &[f64], days: &[f64]) -> f64 {
+    values
+        .iter()
+        .zip(days)
+        .map(|(v, d)| v / (1.0 + rate).powf((d - days[0]) / 365.0))
+        .sum()
+}
+
+/// The rows `[period, payment, interest, principal, balance]` of paying off
+/// `pv` in `nper` equal payments, signed like `pmt`.
+pub fn amortization(rate: f64, nper: usize, pv: f64) -> Vec<[f64; 5]> {
+    let payment = pmt(rate, nper as f64, pv, 0.0, false);
+    let mut balance = pv;
+    let mut rows = Vec::with_capacity(nper);
+    for period in 1..=nper {
+        let interest = -balance * rate;
+        let principal = payment - interest;
+        balance += principal;
+        // what rounding leaves of the loan at the end
+        if period == nper && balance.abs() < 1e-9 * pv.abs() {
+            balance = 0.0;
+        }
+        rows.push([period as f64, payment, interest, principal, balance]);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cents(x: f64) -> f64 {
+        (x * 100.0).round() / 100.0
+    }
+
+    #[test]
+    fn test_annuities() {
+        // a 30 year mortgage of 200000 at 5% a year
+        assert_eq!(
+            cents(pmt(0.05 / 12.0, 360.0, 200000.0, 0.0, false)),
+            -1073.64
+        );
+        assert_eq!(
+            cents(pv(0.05 / 12.0, 360.0, -1073.64, 0.0, false)),
+            199999.4
+        );
+        assert_eq!(cents(fv(0.06 / 12.0, 10.0, -200.0, -500.0, true)), 2581.40);
+        assert_eq!(pmt(0.0, 10.0, 1000.0, 0.0, false), -100.0);
+        let n = nper(0.01, -100.0, -1000.0, 10000.0, true).unwrap();
+        assert!((n - 59.6738656742946).abs() < 1e-9);
+        let r = rate(48.0, -200.0, 8000.0, 0.0, false, 0.1).unwrap();
+        assert!((r - 0.007701472488).abs() < 1e-12);
+        assert_eq!(nper(0.1, -10.0, 1000.0, 0.0, false), None);
+    }
+
+    #[test]
+    fn test_cash_flows() {
+        let values = [10000.0, 3000.0, 4200.0, 6800.0];
+        assert_eq!(
+            cents(npv(0.1, &[-10000.0, 3000.0, 4200.0, 6800.0])),
+            1188.44
+        );
+        assert_eq!(cents(npv(0.1, &values[1..]) - 10000.0), 1307.29);
+        let r = irr(
+            &[-70000.0, 12000.0, 15000.0, 18000.0, 21000.0, 26000.0],
+            0.1,
+        )
+        .unwrap();
+        assert!((r - 0.08663094803653162).abs() < 1e-12);
+        assert_eq!(irr(&[100.0, 200.0], 0.1), None);
+        let days = [0.0, 60.0, 303.0, 411.0, 456.0];
+        let values = [-10000.0, 2750.0, 4250.0, 3250.0, 2750.0];
+        assert_eq!(cents(xnpv(0.09, &values, &days)), 2086.65);
+    }
+
+    #[test]
+    fn test_amortization() {
+        let rows = amortization(0.01, 3, 1000.0);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(cents(rows[0][1]), -340.02);
+        assert_eq!(cents(rows[0][2]), -10.0);
+        assert_eq!(rows[2][4], 0.0);
+        let paid: f64 = rows.iter().map(|row| row[3]).sum();
+        assert!((paid + 1000.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,292 @@
+use anyhow::{bail, Result};
+
+use crate::errors::CaculatorError;
+use crate::rational::Rational;
+
+/// How the digits of a real number are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Notation {
+    /// The shortest digits that read back as the same `f64`.
+    #[default]
+    Shortest,
+    /// A fixed number of decimals: `3.14`.
+    Fixed(usize),
+    /// A number of significant digits: `3.142`.
+    Significant(usize),
+    /// Decimals of the mantissa in scientific notation: `3.14e2`.
+    Scientific(usize),
+    /// Like scientific, but with exponents that are multiples of three: `314.16e0`.
+    Engineering(usize),
+    /// The fraction agreeing with the value to this many decimals: `5/4`.
+    Fraction(usize),
+    /// Like fraction, written as a decimal with its period in parentheses: `0.(142857)`.
+    Repeating(usize),
+}
+
+impl Notation {
+    /// The same notation with `digits` instead of its current digit count.
+    pub fn with_digits(self, digits: usize) -> Self {
+        match self {
+            Notation::Shortest => Notation::Shortest,
+            Notation::Fixed(_) => Notation::Fixed(digits),
+            Notation::Significant(_) => Notation::Significant(digits),
+            Notation::Scientific(_) => Notation::Scientific(digits),
+            Notation::Engineering(_) => Notation::Engineering(digits),
+            Notation::Fraction(_) => Notation::Fraction(digits),
+            Notation::Repeating(_) => Notation::Repeating(digits),
+        }
+    }
+}
+
+/// Formatting options for results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Format {
+    pub notation: Notation,
+    /// Separates thousands in the integer part: `1,234,567`.
+    pub grouping: bool,
+}
+
+// plain digits are switched to an exponent outside of this range in shortest mode
+const SHORTEST_MAX: f64 = 1e21;
+const SHORTEST_MIN: f64 = 1e-7;
+
+// periods are cut off after this many digits
+pub const MAX_PERIOD: usize = 64;
+
+impl Format {
+    pub fn real(&self, x: f64) -> String {
+        if !x.is_finite() {
+            return x.to_string();
+        }
+        let s = match self.notation {
+            Notation::Shortest => {
+                let abs = x.abs();
+                if abs != 0.0 && !(SHORTEST_MIN..SHORTEST_MAX).contains(&abs) {
+                    format!("{:e}", x)
+                } else {
+                    x.to_string()
+                }
+            }
+            Notation::Fixed(decimals) => format!("{:.*}", decimals, x),
+            Notation::Significant(digits) => significant(x, digits.max(1)),
+            Notation::Scientific(decimals) => format!("{:.*e}", decimals, x),
+            Notation::Engineering(decimals) => engineering(x, decimals),
+            Notation::Fraction(decimals) | Notation::Repeating(decimals) => {
+                let tolerance = 10f64.powi(-(decimals as i32)) * x.abs().max(1.0);
+                let fraction = match Rational::approximate(x, tolerance) {
+                    Ok(fraction) => fraction,
+                    Err(_) => return Format::default().real(x),
+                };
+                match self.notation {
+                    Notation::Repeating(_) => fraction.to_repeating_decimal(MAX_PERIOD),
+                    _ => fraction.to_string(),
+                }
+            }
+        };
+        if self.grouping {
+            return group(&s);
+        }
+        s
+    }
+
+    /// Inserts thousands separators if grouping is on, for exact results
+    /// that are already written out.
+    pub fn digits(&self, s: &str) -> String {
+        if self.grouping {
+            return group(s);
+        }
+        s.to_string()
+    }
+}
+
+/// Writes `x` in base `radix` (2 to 36) with upper case digits, e.g. `FF.8`
+/// in base 16. Fractions are cut off after about the precision of an `f64`.
+pub fn radix(x: f64, radix: u32) -> Result<String> {
+    check_radix(radix)?;
+    if !x.is_finite() {
+        return Ok(x.to_string());
+    }
+
+    let base = radix as f64;
+    let mut int = x.abs().trunc();
+    let mut frac = x.abs() - int;
+
+    let mut digits = Vec::new();
+    loop {
+        digits.push(digit(int % base));
+        int = ((int - int % base) / base).trunc();
+        if int == 0.0 {
+            break;
+        }
+    }
+    digits.reverse();
+
+    if frac > 0.0 {
+        digits.push('.');
+        for _ in 0..fraction_digits(radix) {
+            frac *= base;
+            digits.push(digit(frac.trunc()));
+            frac = frac.fract();
+            if frac == 0.0 {
+                break;
+            }
+        }
+        while digits.last() == Some(&'0') {
+            digits.pop();
+        }
+    }
+
+    let sign = if x < 0.0 { "-" } else { "" };
+    Ok(format!(
+        "{}{}",
+        sign,
+        digits.into_iter().collect::<String>()
+    ))
+}
+
+pub fn check_radix(radix: u32) -> Result<()> {
+    if !(2..=36).contains(&radix) {
+        bail!(CaculatorError::InvalidRadix(radix));
+    }
+    Ok(())
+}
+
+/// How many fraction digits in base `radix` cover the 52 bits of an `f64` mantissa.
+pub fn fraction_digits(radix: u32) -> usize {
+    (52.0 / (radix as f64).log2()).ceil() as usize
+}
+
+fn digit(d: f64) -> char {
+    std::char::from_digit(d as u32, 36)
+        .unwrap_or('?')
+        .to_ascii_uppercase()
+}
+
+// splits `{:e}` output into mantissa and decimal exponent
+fn split_exp(s: &str) -> (&str, i32) {
+    let (mantissa, exp) = s.split_once('e').unwrap_or((s, "0"));
+    (mantissa, exp.parse().unwrap_or(0))
+}
+
+pub(crate) fn significant(x: f64, digits: usize) -> String {
+    if x == 0.0 {
+        return format!("{:.*}", digits - 1, 0.0);
+    }
+    // round in scientific notation first, so 9.99 to 2 digits becomes 10
+    let sci = format!("{:.*e}", digits - 1, x);
+    let (mantissa, exp) = split_exp(&sci);
+    let decimals = (digits as i32 - 1 - exp).max(0) as usize;
+    let rounded: f64 = format!("{}e{}", mantissa, exp).parse().unwrap_or(x);
+    format!("{:.*}", decimals, rounded)
+}
+
+fn engineering(x: f64, decimals: usize) -> String {
+    if x == 0.0 {
+        return format!("{:.*}e0", decimals, 0.0);
+    }
+    let (_, exp) = split_exp(&format!("{:e}", x));
+    let mut exp = exp.div_euclid(3) * 3;
+    let mut mantissa = format!("{:.*}", decimals, x / 10f64.powi(exp));
+    // rounding may carry the mantissa up to 1000
+    if mantissa.trim_start_matches('-').starts_with("1000") {
+        exp += 3;
+        mantissa = format!("{:.*}", decimals, x / 10f64.powi(exp));
+    }
+    format!("{}e{}", mantissa, exp)
+}
+
+// groups the first run of integer digits: `-1234567.5` becomes `-1,234,567.5`
+fn group(s: &str) -> String {
+    let start = s.find(|c: char| c.is_ascii_digit()).unwrap_or(s.len());
+    let end = s[start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(s.len(), |i| start + i);
+    let digits = &s[start..end];
+
+    let mut grouped = String::new();
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(ch);
+    }
+    format!("{}{}{}", &s[..start], grouped, &s[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(notation: Notation) -> Format {
+        Format {
+            notation,
+            grouping: false,
+        }
+    }
+
+    #[test]
+    fn test_notations() {
+        let t_cases = vec![
+            (Notation::Shortest, 1.2499999999999998, "1.2499999999999998"),
+            (Notation::Shortest, 1e20, "100000000000000000000"),
+            (Notation::Shortest, 1e21, "1e21"),
+            (Notation::Shortest, 1.5e-8, "1.5e-8"),
+            (Notation::Fixed(2), 1.2499999999999998, "1.25"),
+            (Notation::Fixed(0), -2.5, "-2"),
+            (Notation::Significant(3), 1.23456, "1.23"),
+            (Notation::Significant(2), 9.99, "10"),
+            (Notation::Significant(3), 0.00012345, "0.000123"),
+            (Notation::Significant(2), 123456.0, "120000"),
+            (Notation::Scientific(2), 123456.0, "1.23e5"),
+            (Notation::Scientific(1), -0.00042, "-4.2e-4"),
+            (Notation::Engineering(2), 123456.0, "123.46e3"),
+            (Notation::Engineering(1), 0.00042, "420.0e-6"),
+            (Notation::Engineering(0), 999.7, "1e3"),
+            (Notation::Fixed(2), f64::INFINITY, "inf"),
+            (Notation::Fraction(9), 1.2499999999999998, "5/4"),
+            (Notation::Fraction(6), 0.333333, "1/3"),
+            (Notation::Fraction(9), 0.1 + 0.2, "3/10"),
+            (Notation::Repeating(9), 1.0 / 7.0, "0.(142857)"),
+            (Notation::Repeating(9), -1.0 / 6.0, "-0.1(6)"),
+            (Notation::Repeating(9), 1e30, "1e30"),
+        ];
+
+        for tc in t_cases {
+            assert_eq!(fmt(tc.0).real(tc.1), tc.2, "{:?} of {}", tc.0, tc.1);
+        }
+    }
+
+    #[test]
+    fn test_radix() -> anyhow::Result<()> {
+        let t_cases = vec![
+            (255.0, 16, "FF"),
+            (255.5, 16, "FF.8"),
+            (-10.0, 2, "-1010"),
+            (8.0, 8, "10"),
+            (0.0, 2, "0"),
+            (0.625, 2, "0.101"),
+            (35.0, 36, "Z"),
+            (1e20, 16, "56BC75E2D63100000"),
+        ];
+        for tc in t_cases {
+            assert_eq!(radix(tc.0, tc.1)?, tc.2, "{} in base {}", tc.0, tc.1);
+        }
+
+        // 0.1 has no finite binary expansion
+        assert_eq!(radix(0.1, 2)?.len(), 2 + fraction_digits(2));
+        assert!(radix(1.0, 1).is_err());
+        assert!(radix(1.0, 37).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_grouping() {
+        let format = Format {
+            notation: Notation::Fixed(1),
+            grouping: true,
+        };
+        assert_eq!(format.real(-1234567.25), "-1,234,567.2");
+        assert_eq!(format.real(999.0), "999.0");
+        assert_eq!(format.digits("1000000/3"), "1,000,000/3");
+    }
+}
@@ -0,0 +1,279 @@
+use anyhow::{bail, Result};
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::fmt;
+
+use crate::errors::CaculatorError;
+use crate::format::{self, Format, Notation};
+
+/// A real number known to lie in `[lo, hi]`, written `interval(lo, hi)` as
+/// a bracket pair is a vector.
+///
+/// Values written as `9.81 ± 0.02` are kept as intervals too, and printed
+/// back as a midpoint and radius as long as every operand was one.
+/// Intervals are plain numbers: combining one with a quantity fails rather
+/// than dropping the unit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+    pub uncertain: bool,
+}
+
+impl Interval {
+    pub fn new(lo: f64, hi: f64) -> Result<Self> {
+        if lo.is_nan() || hi.is_nan() || lo > hi {
+            bail!(CaculatorError::InvalidExpression(format!(
+                "[{}, {}]",
+                lo, hi
+            )));
+        }
+        Ok(Self {
+            lo,
+            hi,
+            uncertain: false,
+        })
+    }
+
+    pub fn point(x: f64) -> Self {
+        Self {
+            lo: x,
+            hi: x,
+            uncertain: false,
+        }
+    }
+
+    /// `mid ± radius`
+    pub fn uncertain(mid: f64, radius: f64) -> Result<Self> {
+        let radius = radius.abs();
+        Ok(Self {
+            uncertain: true,
+            ..Self::new(mid - radius, mid + radius)?
+        })
+    }
+
+    pub fn mid(&self) -> f64 {
+        self.lo + (self.hi - self.lo) / 2.0
+    }
+
+    pub fn radius(&self) -> f64 {
+        (self.hi - self.lo) / 2.0
+    }
+
+    pub fn width(&self) -> f64 {
+        self.hi - self.lo
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.lo <= x && x <= self.hi
+    }
+
+    // the smallest interval holding all of `values`, uncertain if either operand was
+    fn hull(&self, rhs: &Self, values: &[f64]) -> Result<Self> {
+        let lo = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let hi = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Ok(Self {
+            uncertain: self.uncertain || rhs.uncertain,
+            ..Self::new(lo, hi)?
+        })
+    }
+
+    fn map(&self, lo: f64, hi: f64) -> Result<Self> {
+        self.hull(self, &[lo, hi])
+    }
+
+    pub fn add(&self, rhs: &Self) -> Result<Self> {
+        self.hull(rhs, &[self.lo + rhs.lo, self.hi + rhs.hi])
+    }
+
+    pub fn sub(&self, rhs: &Self) -> Result<Self> {
+        self.hull(rhs, &[self.lo - rhs.hi, self.hi - rhs.lo])
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Result<Self> {
+        self.hull(
+            rhs,
+            &[
+                self.lo * rhs.lo,
+                self.lo * rhs.hi,
+                self.hi * rhs.lo,
+                self.hi * rhs.hi,
+            ],
+        )
+    }
+
+    pub fn div(&self, rhs: &Self) -> Result<Self> {
+        if rhs.contains(0.0) {
+            bail!(CaculatorError::DivideByZero);
+        }
+        self.hull(
+            rhs,
+            &[
+                self.lo / rhs.lo,
+                self.lo / rhs.hi,
+                self.hi / rhs.lo,
+                self.hi / rhs.hi,
+            ],
+        )
+    }
+
+    pub fn neg(&self) -> Result<Self> {
+        self.map(-self.hi, -self.lo)
+    }
+
+    pub fn pow(&self, rhs: &Self) -> Result<Self> {
+        // integer powers are defined for negative bases too
+        if rhs.width() == 0.0 && rhs.lo.fract() == 0.0 {
+            let n = rhs.lo;
+            if n < 0.0 {
+                let positive = self.pow(&Self::point(-n))?;
+                return Self::point(1.0).div(&positive).map(|r| Self {
+                    uncertain: r.uncertain || rhs.uncertain,
+                    ..r
+                });
+            }
+            let (a, b) = (self.lo.powf(n), self.hi.powf(n));
+            if n % 2.0 == 0.0 && self.contains(0.0) {
+                return self.hull(rhs, &[0.0, a.max(b)]);
+            }
+            return self.hull(rhs, &[a, b]);
+        }
+        if self.lo < 0.0 {
+            bail!(CaculatorError::NotReal(format!("{}^{}", self, rhs)));
+        }
+        self.hull(
+            rhs,
+            &[
+                self.lo.powf(rhs.lo),
+                self.lo.powf(rhs.hi),
+                self.hi.powf(rhs.lo),
+                self.hi.powf(rhs.hi),
+            ],
+        )
+    }
+
+    /// Applies a function of one real argument to every point of the interval.
+    pub fn call(&self, name: &str) -> Result<Self> {
+        let not_real = || CaculatorError::NotReal(format!("{}({})", name, self));
+        match name {
+            "sqrt" if self.lo >= 0.0 => self.map(self.lo.sqrt(), self.hi.sqrt()),
+            "ln" if self.lo > 0.0 => self.map(self.lo.ln(), self.hi.ln()),
+            "log" if self.lo > 0.0 => self.map(self.lo.log10(), self.hi.log10()),
+            "asin" if self.lo >= -1.0 && self.hi <= 1.0 => self.map(self.lo.asin(), self.hi.asin()),
+            "acos" if self.lo >= -1.0 && self.hi <= 1.0 => self.map(self.hi.acos(), self.lo.acos()),
+            "sqrt" | "ln" | "log" | "asin" | "acos" => bail!(not_real()),
+            "exp" => self.map(self.lo.exp(), self.hi.exp()),
+            "atan" => self.map(self.lo.atan(), self.hi.atan()),
+            "floor" => self.map(self.lo.floor(), self.hi.floor()),
+            "ceil" => self.map(self.lo.ceil(), self.hi.ceil()),
+            "abs" if self.contains(0.0) => self.map(0.0, self.lo.abs().max(self.hi)),
+            "abs" => self.map(self.lo.abs(), self.hi.abs()),
+            "sin" => self.sin(),
+            "cos" => Self {
+                lo: self.lo + FRAC_PI_2,
+                hi: self.hi + FRAC_PI_2,
+                ..*self
+            }
+            .sin(),
+            "tan" => {
+                // tan is increasing between its poles at pi/2 + k*pi
+                let pole = ((self.lo - FRAC_PI_2) / PI).floor() + 1.0;
+                if FRAC_PI_2 + pole * PI <= self.hi {
+                    bail!(not_real());
+                }
+                self.map(self.lo.tan(), self.hi.tan())
+            }
+            _ => bail!(CaculatorError::UnknownFunction(name.to_string())),
+        }
+    }
+
+    fn sin(&self) -> Result<Self> {
+        // the maximum lies inside if some pi/2 + 2k*pi does, the minimum for -pi/2 + 2k*pi
+        let has = |offset: f64| {
+            let k = ((self.lo - offset) / (2.0 * PI)).ceil();
+            offset + k * 2.0 * PI <= self.hi
+        };
+        let (a, b) = (self.lo.sin(), self.hi.sin());
+        let hi = if has(FRAC_PI_2) { 1.0 } else { a.max(b) };
+        let lo = if has(-FRAC_PI_2) { -1.0 } else { a.min(b) };
+        self.map(lo, hi)
+    }
+
+    /// Writes `[lo, hi]`, or `mid ± radius` for uncertain values. In shortest
+    /// notation the radius is rounded to one or two significant digits and the
+    /// midpoint to the same decimal place, as error bars are usually quoted.
+    pub fn format(&self, format: &Format) -> String {
+        if !self.uncertain {
+            return format!("[{}, {}]", format.real(self.lo), format.real(self.hi));
+        }
+        let (mid, radius) = (self.mid(), self.radius());
+        if format.notation != Notation::Shortest || radius == 0.0 || !radius.is_finite() {
+            return format!("{} ± {}", format.real(mid), format.real(radius));
+        }
+
+        // a leading 1 keeps a second digit: 0.15 stays 0.15, 0.23 becomes 0.2
+        let two = format::significant(radius, 2);
+        let radius = if two.trim_start_matches(['0', '.']).starts_with('1') {
+            two
+        } else {
+            format::significant(radius, 1)
+        };
+        let decimals = radius.split_once('.').map_or(0, |(_, d)| d.len());
+        let mid = Format {
+            notation: Notation::Fixed(decimals),
+            ..*format
+        }
+        .real(mid);
+        format!("{} ± {}", mid, format.digits(&radius))
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(&Format::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() -> anyhow::Result<()> {
+        let a = Interval::new(1.0, 2.0)?;
+        let b = Interval::new(-3.0, 4.0)?;
+        assert_eq!(a.add(&b)?, Interval::new(-2.0, 6.0)?);
+        assert_eq!(a.sub(&b)?, Interval::new(-3.0, 5.0)?);
+        assert_eq!(a.mul(&b)?, Interval::new(-6.0, 8.0)?);
+        assert_eq!(b.div(&a)?, Interval::new(-3.0, 4.0)?);
+        assert!(a.div(&b).is_err());
+        assert_eq!(b.pow(&Interval::point(2.0))?, Interval::new(0.0, 16.0)?);
+        assert_eq!(b.pow(&Interval::point(3.0))?, Interval::new(-27.0, 64.0)?);
+        assert_eq!(a.pow(&Interval::point(-1.0))?, Interval::new(0.5, 1.0)?);
+        assert!(b.pow(&Interval::point(0.5)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_functions() -> anyhow::Result<()> {
+        let x = Interval::new(0.0, PI)?;
+        assert_eq!(x.call("sin")?.hi, 1.0);
+        assert_eq!(x.call("cos")?, Interval::new(-1.0, 1.0)?);
+        assert_eq!(
+            Interval::new(-2.0, 1.0)?.call("abs")?,
+            Interval::new(0.0, 2.0)?
+        );
+        assert!(Interval::new(1.0, 2.0)?.call("tan").is_err());
+        assert!(Interval::new(-1.0, 1.0)?.call("sqrt").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format() -> anyhow::Result<()> {
+        assert_eq!(Interval::new(1.0, 2.0)?.to_string(), "[1, 2]");
+        assert_eq!(Interval::uncertain(9.81, 0.02)?.to_string(), "9.81 ± 0.02");
+        assert_eq!(Interval::uncertain(9.81, 0.15)?.to_string(), "9.81 ± 0.15");
+        assert_eq!(Interval::uncertain(1234.0, 56.0)?.to_string(), "1234 ± 60");
+        assert_eq!(Interval::uncertain(2.0, 0.0)?.to_string(), "2 ± 0");
+        Ok(())
+    }
+}
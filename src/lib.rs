@@ -1,3 +1,25 @@
+pub mod bigint;
 pub mod calculator;
+pub mod calculus;
+pub mod combinatorics;
+pub mod complex;
+pub mod context;
+pub mod currency;
+pub mod datetime;
 pub mod errors;
+pub mod expr;
+pub mod finance;
+pub mod format;
+pub mod interval;
 pub mod keypad;
+pub mod matrix;
+pub mod number;
+pub mod numtheory;
+pub mod poly;
+pub mod random;
+pub mod rational;
+pub mod solve;
+pub mod stats;
+pub mod units;
+pub mod value;
+pub mod word;
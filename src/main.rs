@@ -1,4 +1,17 @@
-use calc::{calculator::Caculator, keypad};
+use std::sync::Arc;
+
+use calc::{
+    calculator::Caculator,
+    complex::Complex,
+    context::{AngleMode, Context},
+    currency::RateTable,
+    format::{Format, Notation},
+    keypad,
+    number::Number,
+    rational::Rational,
+    value::Value,
+    word::{self, IntType, OverflowMode, WordMode},
+};
 // it's an example
 use eframe::egui;
 
@@ -28,15 +41,205 @@ fn main() -> eframe::Result {
     )
 }
 
+// the numeric backend the expression is evaluated over
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    Complex,
+    Real,
+    Exact,
+    // fixed-width integers, over exact numbers so `u64` fits
+    Programmer,
+}
+
 struct MyApp {
     name: String,
     age: u32,
     keypad: Keypad,
     exp: String,
     result: String,
+    value: Option<Complex>,
+    polar: bool,
+    backend: Backend,
+    context: Context,
+    format: Format,
+    // digits of every notation but the shortest
+    digits: usize,
+    rates_path: String,
+    rates: Option<Arc<RateTable>>,
+    rates_date: Option<String>,
+    // the error of numeric integrals and derivatives in the result
+    error_estimate: Option<f64>,
+    // the result in other bases, shown below it
+    radixes: Vec<(String, String)>,
+    radix: u32,
+    // the integer type of the programmer backend and its result, for the bit grid
+    word: WordMode,
+    int: Option<i128>,
 }
 
-impl MyApp {}
+impl MyApp {
+    // evaluates the expression over the backend `N` with the loaded exchange rates,
+    // returning the date of the rates if the expression used them
+    fn run<N: Number>(&mut self, exp: &str) -> anyhow::Result<(Value<N>, Option<String>)> {
+        let context = Context {
+            word: (self.backend == Backend::Programmer).then_some(self.word),
+            ..self.context
+        };
+        let mut caculator = Caculator::<N>::with_number(exp.to_string()).with_context(context);
+        if let Some(rates) = &self.rates {
+            caculator = caculator.with_rates(rates.clone());
+        }
+        let v = caculator.evaluate();
+        self.error_estimate = caculator.error_estimate();
+        let v = v?;
+        Ok((v, caculator.rates_date().map(|d| d.to_string())))
+    }
+
+    // the result in decimal, hexadecimal, octal, binary and the chosen radix,
+    // or nothing if it is not a real number
+    fn radixes<N: Number>(&self, v: &Value<N>) -> Vec<(String, String)> {
+        let bases = [
+            ("hex".to_string(), 16),
+            ("oct".to_string(), 8),
+            ("bin".to_string(), 2),
+            (format!("base {}", self.radix), self.radix),
+        ];
+        let mut radixes = vec![("dec".to_string(), v.format(&self.format))];
+        // the programmer backend shows two's complement patterns
+        let digits = |radix| match (self.backend, v) {
+            (Backend::Programmer, Value::Num(n)) => self.word.radix(word::to_int(n)?, radix),
+            _ => v.radix(radix),
+        };
+        for (name, radix) in bases {
+            match digits(radix) {
+                Ok(digits) => radixes.push((name, digits)),
+                Err(_) => return Vec::new(),
+            }
+        }
+        radixes
+    }
+
+    fn evaluate(&mut self) {
+        self.evaluate_exp(&self.exp.clone());
+    }
+
+    // shows the derivative of the input with respect to `x`
+    fn differentiate(&mut self) {
+        self.evaluate_exp(&format!("diff({}, x)", self.exp));
+    }
+
+    // shows the input rewritten in a simpler form
+    fn simplify(&mut self) {
+        self.evaluate_exp(&format!("simplify({})", self.exp));
+    }
+
+    fn evaluate_exp(&mut self, exp: &str) {
+        let mut value = None;
+        let mut int = None;
+        let result = match self.backend {
+            Backend::Complex => self.run::<Complex>(exp).map(|(v, date)| {
+                // polar display only applies to plain numbers
+                if let Value::Num(z) = v {
+                    value = Some(z);
+                }
+                (v.format(&self.format), date, self.radixes(&v))
+            }),
+            Backend::Real => self
+                .run::<f64>(exp)
+                .map(|(v, date)| (v.format(&self.format), date, self.radixes(&v))),
+            Backend::Exact => self
+                .run::<Rational>(exp)
+                .map(|(v, date)| (v.format(&self.format), date, self.radixes(&v))),
+            Backend::Programmer => self.run::<Rational>(exp).map(|(v, date)| {
+                if let Value::Num(n) = &v {
+                    int = word::to_int(n).ok();
+                }
+                (v.format(&self.format), date, self.radixes(&v))
+            }),
+        };
+        self.value = value;
+        self.int = int;
+        (self.result, self.rates_date, self.radixes) = match result {
+            Ok(r) => r,
+            Err(e) => (e.to_string(), None, Vec::new()),
+        };
+        self.show_polar();
+    }
+
+    // writes a complex result in the chosen form; only on changes, so
+    // messages such as the one of loading rates stay until the next one
+    fn show_polar(&mut self) {
+        if let Some(z) = self.value {
+            self.result = if self.polar {
+                z.to_polar_string()
+            } else {
+                z.format(&self.format)
+            };
+        }
+    }
+
+    // the integer type and overflow choices, and the bits of the result:
+    // clicking a bit toggles it and evaluates the new number
+    fn programmer(&mut self, ui: &mut egui::Ui) {
+        let old = self.word;
+        ui.horizontal(|ui| {
+            for ty in IntType::ALL {
+                ui.radio_value(&mut self.word.ty, ty, ty.to_string());
+            }
+        });
+        ui.horizontal(|ui| {
+            let overflow = &mut self.word.overflow;
+            ui.radio_value(overflow, OverflowMode::Wrapping, "wrapping");
+            ui.radio_value(overflow, OverflowMode::Saturating, "saturating");
+            ui.radio_value(overflow, OverflowMode::Checked, "checked");
+        });
+        if self.word != old && !self.exp.is_empty() {
+            self.evaluate();
+        }
+
+        let Some(x) = self.int else {
+            return;
+        };
+        let bits = self.word.bits(x);
+        let mut toggled = None;
+        egui::Grid::new("bits").show(ui, |ui| {
+            // up to 16 bits a row, the highest first
+            let width = self.word.ty.bits.min(16);
+            for row in (0..self.word.ty.bits / width).rev() {
+                ui.label(format!("{}", row * width + width - 1));
+                for i in (row * width..(row + 1) * width).rev() {
+                    let set = bits >> i & 1 == 1;
+                    if ui
+                        .selectable_label(set, if set { "1" } else { "0" })
+                        .clicked()
+                    {
+                        toggled = Some(i);
+                    }
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(i) = toggled {
+            self.exp = self.word.from_bits(bits ^ 1 << i).to_string();
+            self.evaluate();
+        }
+    }
+
+    fn load_rates(&mut self) {
+        // the message replaces the result, which the polar switch must not bring back
+        self.value = None;
+        match RateTable::load(&self.rates_path) {
+            Ok(rates) => {
+                self.result = format!("loaded {} rates of {}", rates.base, rates.date);
+                self.rates = Some(Arc::new(rates));
+            }
+            Err(e) => {
+                self.result = e.to_string();
+                self.rates = None;
+            }
+        }
+    }
+}
 
 impl Default for MyApp {
     fn default() -> Self {
@@ -46,6 +249,20 @@ impl Default for MyApp {
             keypad: Keypad::new(),
             exp: String::new(),
             result: String::new(),
+            value: None,
+            polar: false,
+            backend: Backend::Complex,
+            context: Context::default(),
+            format: Format::default(),
+            digits: 6,
+            rates_path: String::new(),
+            rates: None,
+            rates_date: None,
+            error_estimate: None,
+            radixes: Vec::new(),
+            radix: 36,
+            word: WordMode::default(),
+            int: None,
         }
     }
 }
@@ -68,19 +285,19 @@ impl eframe::App for MyApp {
 
                 ui.horizontal(|ui| {
                     if ui.button("+").clicked() {
-                        self.exp.push_str("+");
+                        self.exp.push('+');
                     }
                     if ui.button("-").clicked() {
-                        self.exp.push_str("-");
+                        self.exp.push('-');
                     }
                     if ui.button("*").clicked() {
-                        self.exp.push_str("*");
+                        self.exp.push('*');
                     }
                     if ui.button("/").clicked() {
-                        self.exp.push_str("/");
+                        self.exp.push('/');
                     }
                     if ui.button("^").clicked() {
-                        self.exp.push_str("^");
+                        self.exp.push('^');
                     }
                 });
 
@@ -89,10 +306,19 @@ impl eframe::App for MyApp {
                         self.exp.clear();
                     }
                     if ui.button("(").clicked() {
-                        self.exp.push_str("(");
+                        self.exp.push('(');
                     }
                     if ui.button(")").clicked() {
-                        self.exp.push_str(")");
+                        self.exp.push(')');
+                    }
+                    if ui.button("i").clicked() {
+                        self.exp.push('i');
+                    }
+                    if ui.button("√").clicked() {
+                        self.exp.push_str("sqrt(");
+                    }
+                    if ui.button("±").clicked() {
+                        self.exp.push('±');
                     }
                 });
 
@@ -120,26 +346,110 @@ impl eframe::App for MyApp {
                 ui.horizontal(|ui| {
                     let btn = ui.button("0");
                     if btn.clicked() {
-                        self.exp.push_str("0");
+                        self.exp.push('0');
                     }
 
-                    if ui.button(format!(".")).clicked() {
-                        self.exp.push_str(".");
+                    if ui.button(".").clicked() {
+                        self.exp.push('.');
                     }
                     if ui.button("=").clicked() {
-                        match Caculator::new(self.exp.clone()).calculate() {
-                            Ok(v) => {
-                                self.result = v.to_string();
-                            }
-                            Err(e) => {
-                                self.result = e.to_string();
-                            }
-                        }
+                        self.evaluate();
+                    }
+                    if ui.button("d/dx").clicked() {
+                        self.differentiate();
+                    }
+                    if ui.button("simplify").clicked() {
+                        self.simplify();
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.backend, Backend::Complex, "complex");
+                    ui.radio_value(&mut self.backend, Backend::Real, "real");
+                    ui.radio_value(&mut self.backend, Backend::Exact, "exact");
+                    ui.radio_value(&mut self.backend, Backend::Programmer, "programmer");
+                });
+                if self.backend == Backend::Programmer {
+                    self.programmer(ui);
+                }
+                ui.horizontal(|ui| {
+                    let angle = &mut self.context.angle;
+                    ui.radio_value(angle, AngleMode::Degrees, "deg");
+                    ui.radio_value(angle, AngleMode::Radians, "rad");
+                    ui.radio_value(angle, AngleMode::Gradians, "grad");
+                    ui.checkbox(&mut self.context.strict, "fail on inf/NaN");
+                });
+                ui.horizontal(|ui| {
+                    // a fixed seed makes the random functions repeat their numbers
+                    let mut fixed = self.context.seed.is_some();
+                    ui.checkbox(&mut fixed, "fixed seed");
+                    if fixed {
+                        let seed = self.context.seed.get_or_insert(0);
+                        ui.add(egui::DragValue::new(seed));
+                    } else {
+                        self.context.seed = None;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let old = self.format;
+                    let notation = &mut self.format.notation;
+                    let digits = self.digits;
+                    ui.radio_value(notation, Notation::Shortest, "shortest");
+                    ui.radio_value(notation, Notation::Fixed(digits), "fixed");
+                    ui.radio_value(notation, Notation::Significant(digits), "significant");
+                    ui.radio_value(notation, Notation::Scientific(digits), "scientific");
+                    ui.radio_value(notation, Notation::Engineering(digits), "engineering");
+                    ui.radio_value(notation, Notation::Fraction(digits), "fraction");
+                    ui.radio_value(notation, Notation::Repeating(digits), "repeating");
+                    ui.add(egui::DragValue::new(&mut self.digits).range(0..=17));
+                    ui.checkbox(&mut self.format.grouping, "grouping");
+                    self.format.notation = self.format.notation.with_digits(self.digits);
+                    if self.format != old && !self.exp.is_empty() {
+                        self.evaluate();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let old = self.polar;
+                    ui.radio_value(&mut self.polar, false, "rectangular");
+                    ui.radio_value(&mut self.polar, true, "polar");
+                    if self.polar != old {
+                        self.show_polar();
                     }
                 });
 
                 ui.label(format!("Hello '{}', age {}", self.name, self.age));
                 ui.label(format!("calculate result: {}", self.result));
+                if !self.radixes.is_empty() {
+                    egui::Grid::new("radixes").show(ui, |ui| {
+                        for (name, digits) in &self.radixes {
+                            ui.label(name);
+                            ui.label(digits);
+                            ui.end_row();
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("radix:");
+                    let radix = ui.add(egui::DragValue::new(&mut self.radix).range(2..=36));
+                    if radix.changed() && !self.exp.is_empty() {
+                        self.evaluate();
+                    }
+                });
+                ui.label(format!("angle mode: {}", self.context.angle));
+                if let Some(error) = self.error_estimate {
+                    ui.label(format!("error estimate: ±{:.1e}", error));
+                }
+                if let Some(date) = &self.rates_date {
+                    ui.label(format!("exchange rates of {}", date));
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("rates file:");
+                    ui.text_edit_singleline(&mut self.rates_path);
+                    if ui.button("load").clicked() {
+                        self.load_rates();
+                    }
+                });
             });
 
         // self.keypad.show(ctx);
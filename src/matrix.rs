@@ -0,0 +1,447 @@
+use anyhow::{bail, Result};
+use std::fmt;
+
+use crate::errors::CaculatorError;
+use crate::format::Format;
+use crate::number::Number;
+
+// on float backends, entries this small next to the largest one are zero
+const TOLERANCE: f64 = 1e-12;
+
+/// A dense matrix in row-major order. Vectors are columns of a single
+/// matrix column, flagged so they print as `[1, 2, 3]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<N> {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<N>,
+    pub vector: bool,
+}
+
+impl<N: Number> Matrix<N> {
+    pub fn new(rows: usize, cols: usize, data: Vec<N>) -> Result<Self> {
+        if rows * cols != data.len() || data.is_empty() {
+            bail!(CaculatorError::ShapeMismatch(
+                format!("{}x{}", rows, cols),
+                data.len().to_string()
+            ));
+        }
+        Ok(Self {
+            rows,
+            cols,
+            data,
+            vector: false,
+        })
+    }
+
+    pub fn vector(data: Vec<N>) -> Result<Self> {
+        Ok(Self {
+            vector: true,
+            ..Self::new(data.len(), 1, data)?
+        })
+    }
+
+    /// Stacks vectors of equal length as the rows of a matrix.
+    pub fn from_rows(rows: &[Matrix<N>]) -> Result<Self> {
+        let Some(first) = rows.first() else {
+            bail!(CaculatorError::InvalidExpression("[]".to_string()));
+        };
+        let mut data = Vec::new();
+        for row in rows {
+            if !row.vector || row.rows != first.rows {
+                bail!(CaculatorError::ShapeMismatch(first.shape(), row.shape()));
+            }
+            data.extend(row.data.iter().cloned());
+        }
+        Self::new(rows.len(), first.rows, data)
+    }
+
+    pub fn identity(n: usize) -> Result<Self> {
+        let (zero, one) = (N::parse("0")?, N::parse("1")?);
+        let data = (0..n * n)
+            .map(|i| {
+                if i / n == i % n {
+                    one.clone()
+                } else {
+                    zero.clone()
+                }
+            })
+            .collect();
+        Self::new(n, n, data)
+    }
+
+    /// `3` for vectors, `2x3` for matrices.
+    pub fn shape(&self) -> String {
+        if self.vector {
+            return format!("[{}]", self.rows);
+        }
+        format!("[{}x{}]", self.rows, self.cols)
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &N {
+        &self.data[row * self.cols + col]
+    }
+
+    fn mismatch(&self, rhs: &Self) -> CaculatorError {
+        CaculatorError::ShapeMismatch(self.shape(), rhs.shape())
+    }
+
+    pub fn map(&self, f: impl Fn(&N) -> Result<N>) -> Result<Self> {
+        Ok(Self {
+            data: self.data.iter().map(f).collect::<Result<_>>()?,
+            ..self.clone()
+        })
+    }
+
+    /// Combines the entries of two matrices of the same shape.
+    pub fn zip(&self, rhs: &Self, f: impl Fn(&N, &N) -> Result<N>) -> Result<Self> {
+        if (self.rows, self.cols) != (rhs.rows, rhs.cols) {
+            bail!(self.mismatch(rhs));
+        }
+        Ok(Self {
+            data: self
+                .data
+                .iter()
+                .zip(&rhs.data)
+                .map(|(a, b)| f(a, b))
+                .collect::<Result<_>>()?,
+            vector: self.vector && rhs.vector,
+            ..self.clone()
+        })
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Result<Self> {
+        if self.cols != rhs.rows {
+            bail!(self.mismatch(rhs));
+        }
+        let mut data = Vec::with_capacity(self.rows * rhs.cols);
+        for i in 0..self.rows {
+            for j in 0..rhs.cols {
+                let mut acc = N::parse("0")?;
+                for k in 0..self.cols {
+                    acc = acc.add(&self.get(i, k).mul(rhs.get(k, j))?)?;
+                }
+                data.push(acc);
+            }
+        }
+        Ok(Self {
+            vector: rhs.vector,
+            ..Self::new(self.rows, rhs.cols, data)?
+        })
+    }
+
+    /// Raises a square matrix to an integer power, inverting for negative ones.
+    pub fn pow(&self, exp: &N) -> Result<Self> {
+        let n = match exp.to_f64() {
+            Some(n) if n.fract() == 0.0 && n.abs() <= u32::MAX as f64 => n,
+            _ => bail!(CaculatorError::InvalidExpression(format!(
+                "{}^{}",
+                self.shape(),
+                exp
+            ))),
+        };
+        let mut base = if n < 0.0 { self.inv()? } else { self.square()? };
+        let mut acc = Self::identity(self.rows)?;
+        let mut k = n.abs() as u32;
+        while k > 0 {
+            if k & 1 == 1 {
+                acc = acc.mul(&base)?;
+            }
+            k >>= 1;
+            if k > 0 {
+                base = base.mul(&base)?;
+            }
+        }
+        Ok(acc)
+    }
+
+    pub fn transpose(&self) -> Result<Self> {
+        let mut data = Vec::with_capacity(self.data.len());
+        for j in 0..self.cols {
+            for i in 0..self.rows {
+                data.push(self.get(i, j).clone());
+            }
+        }
+        Self::new(self.cols, self.rows, data)
+    }
+
+    pub fn dot(&self, rhs: &Self) -> Result<N> {
+        if !self.vector || !rhs.vector || self.rows != rhs.rows {
+            bail!(self.mismatch(rhs));
+        }
+        let mut acc = N::parse("0")?;
+        for (a, b) in self.data.iter().zip(&rhs.data) {
+            acc = acc.add(&a.mul(b)?)?;
+        }
+        Ok(acc)
+    }
+
+    /// The length `sqrt(sum |x|^2)`, real for complex entries too; for a
+    /// matrix this is the Frobenius norm.
+    pub fn norm(&self) -> Result<N> {
+        let mut acc = N::parse("0")?;
+        for x in &self.data {
+            let abs = N::call("abs", std::slice::from_ref(x))?;
+            acc = acc.add(&abs.mul(&abs)?)?;
+        }
+        N::call("sqrt", &[acc])
+    }
+
+    pub fn cross(&self, rhs: &Self) -> Result<Self> {
+        if !self.vector || !rhs.vector || self.rows != 3 || rhs.rows != 3 {
+            bail!(self.mismatch(rhs));
+        }
+        let (a, b) = (&self.data, &rhs.data);
+        let term = |i: usize, j: usize| a[i].mul(&b[j])?.sub(&a[j].mul(&b[i])?);
+        Self::vector(vec![term(1, 2)?, term(2, 0)?, term(0, 1)?])
+    }
+
+    fn square(&self) -> Result<Self> {
+        if self.rows != self.cols || self.vector {
+            bail!(CaculatorError::ShapeMismatch(
+                self.shape(),
+                "a square matrix".to_string()
+            ));
+        }
+        Ok(self.clone())
+    }
+
+    pub fn det(&self) -> Result<N> {
+        let mut m = self.square()?;
+        let mut det = N::parse("1")?;
+        for col in 0..m.cols {
+            let Some(pivot) = m.pivot(col, col) else {
+                return N::parse("0");
+            };
+            if pivot != col {
+                m.swap_rows(pivot, col);
+                det = det.neg()?;
+            }
+            det = det.mul(m.get(col, col))?;
+            m.eliminate(col, col, false)?;
+        }
+        Ok(det)
+    }
+
+    pub fn inv(&self) -> Result<Self> {
+        let n = self.square()?.rows;
+        self.solve(&Self::identity(n)?)
+    }
+
+    /// Solves `self * x = rhs` by Gauss-Jordan elimination, as in `A \ b`.
+    pub fn solve(&self, rhs: &Self) -> Result<Self> {
+        let n = self.square()?.rows;
+        if rhs.rows != n {
+            bail!(self.mismatch(rhs));
+        }
+
+        // augment with the right hand side and reduce the left block to identity
+        let width = n + rhs.cols;
+        let mut data = Vec::with_capacity(n * width);
+        for i in 0..n {
+            data.extend((0..n).map(|j| self.get(i, j).clone()));
+            data.extend((0..rhs.cols).map(|j| rhs.get(i, j).clone()));
+        }
+        let mut m = Self::new(n, width, data)?;
+        for col in 0..n {
+            let Some(pivot) = m.pivot(col, col) else {
+                bail!(CaculatorError::SingularMatrix);
+            };
+            m.swap_rows(pivot, col);
+            m.eliminate(col, col, true)?;
+        }
+
+        let data = (0..n)
+            .flat_map(|i| (n..width).map(move |j| (i, j)))
+            .map(|(i, j)| m.get(i, j).clone())
+            .collect();
+        Ok(Self {
+            vector: rhs.vector,
+            ..Self::new(n, rhs.cols, data)?
+        })
+    }
+
+    /// Solves `self * x = rhs` for any number of equations, failing unless
+    /// the system has exactly one solution.
+    pub fn solve_system(&self, rhs: &Self) -> Result<Self> {
+        if rhs.rows != self.rows || rhs.cols != 1 {
+            bail!(self.mismatch(rhs));
+        }
+        let (rows, n) = (self.rows, self.cols);
+        let mut data = Vec::with_capacity(rows * (n + 1));
+        for i in 0..rows {
+            data.extend((0..n).map(|j| self.get(i, j).clone()));
+            data.push(rhs.get(i, 0).clone());
+        }
+        let mut m = Self::new(rows, n + 1, data)?;
+        let mut rank = 0;
+        for col in 0..n {
+            let Some(pivot) = m.pivot(rank, col) else {
+                continue;
+            };
+            m.swap_rows(pivot, rank);
+            m.eliminate(rank, col, true)?;
+            rank += 1;
+        }
+
+        // a leftover row reading `0 = c` contradicts the others
+        let scale = m.scale();
+        if (rank..rows).any(|i| !m.negligible(m.get(i, n), scale)) {
+            bail!(CaculatorError::Inconsistent);
+        }
+        if rank < n {
+            bail!(CaculatorError::Underdetermined);
+        }
+        Self::vector((0..n).map(|i| m.get(i, n).clone()).collect())
+    }
+
+    // the row at or below `row` with the largest entry in `col`, if any is nonzero
+    fn pivot(&self, row: usize, col: usize) -> Option<usize> {
+        let size = |i: usize| magnitude(self.get(i, col));
+        let scale = self.scale();
+        (row..self.rows)
+            .filter(|&i| !self.negligible(self.get(i, col), scale))
+            .max_by(|&a, &b| {
+                size(a)
+                    .partial_cmp(&size(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+    }
+
+    // the largest magnitude of any entry
+    fn scale(&self) -> f64 {
+        self.data.iter().filter_map(magnitude).fold(0.0, f64::max)
+    }
+
+    // zero, or on float backends only rounding error next to `scale`
+    fn negligible(&self, x: &N, scale: f64) -> bool {
+        match magnitude(x) {
+            Some(size) if !N::EXACT => size <= TOLERANCE * scale,
+            _ => x.is_zero(),
+        }
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for j in 0..self.cols {
+            self.data.swap(a * self.cols + j, b * self.cols + j);
+        }
+    }
+
+    // clears `col` below the pivot, or in every other row and scaling the
+    // pivot row to 1 with `reduce`
+    fn eliminate(&mut self, row: usize, col: usize, reduce: bool) -> Result<()> {
+        let pivot = self.get(row, col).clone();
+        if reduce {
+            for j in 0..self.cols {
+                let v = self.get(row, j).div(&pivot)?;
+                self.data[row * self.cols + j] = v;
+            }
+        }
+        for i in 0..self.rows {
+            if i == row || (!reduce && i < row) || self.get(i, col).is_zero() {
+                continue;
+            }
+            let factor = self.get(i, col).div(self.get(row, col))?;
+            for j in 0..self.cols {
+                let v = self.get(i, j).sub(&factor.mul(self.get(row, j))?)?;
+                self.data[i * self.cols + j] = v;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn format(&self, format: &Format) -> String {
+        let row = |i: usize| {
+            let entries: Vec<String> = (0..self.cols)
+                .map(|j| self.get(i, j).format(format))
+                .collect();
+            entries.join(", ")
+        };
+        if self.vector {
+            let entries: Vec<String> = self.data.iter().map(|x| x.format(format)).collect();
+            return format!("[{}]", entries.join(", "));
+        }
+        let rows: Vec<String> = (0..self.rows).map(|i| format!("[{}]", row(i))).collect();
+        format!("[{}]", rows.join(", "))
+    }
+}
+
+// the absolute value as an `f64`, if the backend has one
+fn magnitude<N: Number>(x: &N) -> Option<f64> {
+    N::call("abs", std::slice::from_ref(x)).ok()?.to_f64()
+}
+
+impl<N: Number> fmt::Display for Matrix<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(&Format::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rational::Rational;
+
+    fn matrix(rows: usize, cols: usize, data: &[i128]) -> Matrix<Rational> {
+        let data = data.iter().map(|&x| Rational::from_int(x)).collect();
+        Matrix::new(rows, cols, data).unwrap()
+    }
+
+    #[test]
+    fn test_products() -> anyhow::Result<()> {
+        let a = matrix(2, 2, &[1, 2, 3, 4]);
+        assert_eq!(a.mul(&a)?.to_string(), "[[7, 10], [15, 22]]");
+        assert_eq!(a.transpose()?.to_string(), "[[1, 3], [2, 4]]");
+        assert_eq!(a.pow(&Rational::from_int(3))?, a.mul(&a)?.mul(&a)?);
+        assert!(a.mul(&matrix(3, 1, &[1, 2, 3])).is_err());
+
+        let u = Matrix::vector(vec![
+            Rational::from_int(1),
+            Rational::from_int(0),
+            Rational::from_int(0),
+        ])?;
+        let v = Matrix::vector(vec![
+            Rational::from_int(0),
+            Rational::from_int(1),
+            Rational::from_int(0),
+        ])?;
+        assert_eq!(u.cross(&v)?.to_string(), "[0, 0, 1]");
+        assert_eq!(u.dot(&v)?, Rational::from_int(0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_elimination() -> anyhow::Result<()> {
+        let a = matrix(3, 3, &[2, 0, 1, 1, 3, 2, 1, 1, 2]);
+        assert_eq!(a.det()?, Rational::from_int(6));
+        assert_eq!(a.inv()?.mul(&a)?, Matrix::identity(3)?);
+        assert_eq!(matrix(2, 2, &[1, 2, 2, 4]).det()?, Rational::from_int(0));
+        assert!(matrix(2, 2, &[1, 2, 2, 4]).inv().is_err());
+        assert_eq!(matrix(2, 2, &[0, 1, 1, 0]).det()?, Rational::from_int(-1));
+
+        let b = Matrix::vector(vec![
+            Rational::from_int(3),
+            Rational::from_int(6),
+            Rational::from_int(4),
+        ])?;
+        let x = a.solve(&b)?;
+        assert_eq!(a.mul(&x)?, b);
+        assert_eq!(a.solve_system(&b)?, x);
+
+        // three equations, two unknowns, one of them redundant
+        let tall = matrix(3, 2, &[1, 1, 1, -1, 2, 2]);
+        let b = Matrix::vector(vec![
+            Rational::from_int(3),
+            Rational::from_int(1),
+            Rational::from_int(6),
+        ])?;
+        assert_eq!(tall.solve_system(&b)?.to_string(), "[2, 1]");
+        let singular = matrix(2, 2, &[1, 2, 2, 4]);
+        let err = singular.solve_system(&Matrix::vector(vec![Rational::from_int(1); 2])?);
+        assert!(matches!(
+            err.unwrap_err().downcast_ref(),
+            Some(CaculatorError::Inconsistent)
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,352 @@
+use anyhow::{bail, Result};
+use std::cmp::Ordering;
+use std::f64::consts::{E, PI};
+use std::fmt::{Debug, Display};
+
+use crate::complex::Complex;
+use crate::errors::CaculatorError;
+use crate::format::{self, Format, Notation};
+use crate::rational::Rational;
+
+/// The value type a [`Caculator`](crate::calculator::Caculator) evaluates over.
+///
+/// Arithmetic is fallible so exact backends can report overflow or results
+/// they cannot represent instead of silently rounding.
+pub trait Number: Clone + Debug + Display + PartialEq {
+    /// True for backends without rounding, whose zeros are exactly zero.
+    const EXACT: bool = false;
+
+    /// Parses an unsigned numeric literal such as `42` or `1.25`.
+    fn parse(literal: &str) -> Result<Self>;
+
+    /// Converts to a real `f64`, or `None` if the value is not real.
+    fn to_f64(&self) -> Option<f64>;
+
+    fn is_zero(&self) -> bool;
+
+    fn add(&self, rhs: &Self) -> Result<Self>;
+    fn sub(&self, rhs: &Self) -> Result<Self>;
+    fn mul(&self, rhs: &Self) -> Result<Self>;
+    fn div(&self, rhs: &Self) -> Result<Self>;
+    fn pow(&self, rhs: &Self) -> Result<Self>;
+    fn neg(&self) -> Result<Self>;
+
+    /// Looks up a named constant such as `pi`.
+    fn constant(name: &str) -> Result<Self>;
+
+    /// Calls a named function on already evaluated arguments.
+    fn call(name: &str, args: &[Self]) -> Result<Self>;
+
+    /// False for infinities and `NaN`.
+    fn is_finite(&self) -> bool {
+        self.to_f64().is_none_or(f64::is_finite)
+    }
+
+    fn is_nan(&self) -> bool {
+        self.to_f64().is_some_and(f64::is_nan)
+    }
+
+    /// Orders two real numbers, `None` if either is not real or is `NaN`.
+    fn cmp_real(&self, other: &Self) -> Option<Ordering> {
+        self.to_f64()?.partial_cmp(&other.to_f64()?)
+    }
+
+    /// Writes the number with the given notation.
+    fn format(&self, format: &Format) -> String {
+        match self.to_f64() {
+            Some(x) => format.real(x),
+            None => format.digits(&self.to_string()),
+        }
+    }
+
+    /// Writes the number in base `radix`, failing if it is not real.
+    fn radix(&self, radix: u32) -> Result<String> {
+        match self.to_f64() {
+            Some(x) => format::radix(x, radix),
+            None => bail!(CaculatorError::NotReal(self.to_string())),
+        }
+    }
+}
+
+impl Number for f64 {
+    fn parse(literal: &str) -> Result<Self> {
+        Ok(literal.parse()?)
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(*self)
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == 0.0
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self> {
+        Ok(self + rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self> {
+        Ok(self - rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self> {
+        Ok(self * rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self> {
+        Ok(self / rhs)
+    }
+
+    fn pow(&self, rhs: &Self) -> Result<Self> {
+        Ok(self.powf(*rhs))
+    }
+
+    fn neg(&self) -> Result<Self> {
+        Ok(-self)
+    }
+
+    fn constant(name: &str) -> Result<Self> {
+        match name {
+            "pi" => Ok(PI),
+            "e" => Ok(E),
+            _ => bail!(CaculatorError::UnknownIdentifier(name.to_string())),
+        }
+    }
+
+    fn call(name: &str, args: &[Self]) -> Result<Self> {
+        let x = unary(name, args)?;
+        let result = match name {
+            "sqrt" => x.sqrt(),
+            "exp" => x.exp(),
+            "ln" => x.ln(),
+            "log" => x.log10(),
+            "sin" => x.sin(),
+            "cos" => x.cos(),
+            "tan" => x.tan(),
+            "asin" => x.asin(),
+            "acos" => x.acos(),
+            "atan" => x.atan(),
+            "abs" => x.abs(),
+            "floor" => x.floor(),
+            "ceil" => x.ceil(),
+            _ => bail!(CaculatorError::UnknownFunction(name.to_string())),
+        };
+        Ok(result)
+    }
+}
+
+impl Number for Complex {
+    fn parse(literal: &str) -> Result<Self> {
+        Ok(Complex::from(f64::parse(literal)?))
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        self.to_real()
+    }
+
+    fn is_zero(&self) -> bool {
+        *self == Complex::default()
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self> {
+        Ok(*self + *rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self> {
+        Ok(*self - *rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self> {
+        Ok(*self * *rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self> {
+        Ok(*self / *rhs)
+    }
+
+    fn pow(&self, rhs: &Self) -> Result<Self> {
+        Ok(Complex::pow(self, *rhs))
+    }
+
+    fn neg(&self) -> Result<Self> {
+        Ok(-*self)
+    }
+
+    fn constant(name: &str) -> Result<Self> {
+        match name {
+            "i" => Ok(Complex::I),
+            _ => Ok(Complex::from(f64::constant(name)?)),
+        }
+    }
+
+    fn call(name: &str, args: &[Self]) -> Result<Self> {
+        // polar(r, theta) builds a phasor from magnitude and angle in radians
+        if name == "polar" {
+            let [r, theta] = args else {
+                bail!(CaculatorError::ArgumentCount(name.to_string(), 2));
+            };
+            return Ok(Complex::from_polar(r.re, theta.re));
+        }
+
+        let z = unary(name, args)?;
+        let result = match name {
+            "sqrt" => z.sqrt(),
+            "exp" => z.exp(),
+            "ln" => z.ln(),
+            "log" => z.ln() / Complex::from(10f64.ln()),
+            "sin" => z.sin(),
+            "cos" => z.cos(),
+            "tan" => z.tan(),
+            "asin" => z.asin(),
+            "acos" => z.acos(),
+            "atan" => z.atan(),
+            "re" => Complex::from(z.re),
+            "im" => Complex::from(z.im),
+            "abs" => Complex::from(z.abs()),
+            "arg" => Complex::from(z.arg()),
+            "conj" => z.conj(),
+            _ => bail!(CaculatorError::UnknownFunction(name.to_string())),
+        };
+        Ok(result)
+    }
+
+    fn format(&self, format: &Format) -> String {
+        self.format_with(|x| format.real(x))
+    }
+
+    fn is_finite(&self) -> bool {
+        self.re.is_finite() && self.im.is_finite()
+    }
+
+    fn is_nan(&self) -> bool {
+        self.re.is_nan() || self.im.is_nan()
+    }
+}
+
+impl Number for Rational {
+    const EXACT: bool = true;
+
+    fn parse(literal: &str) -> Result<Self> {
+        Rational::parse(literal)
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(Rational::to_f64(self))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.numer() == 0
+    }
+
+    // fractions closer than `f64` can tell apart still compare exactly
+    fn cmp_real(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+
+    fn add(&self, rhs: &Self) -> Result<Self> {
+        self.checked_add(rhs)
+    }
+
+    fn sub(&self, rhs: &Self) -> Result<Self> {
+        self.checked_sub(rhs)
+    }
+
+    fn mul(&self, rhs: &Self) -> Result<Self> {
+        self.checked_mul(rhs)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self> {
+        self.checked_div(rhs)
+    }
+
+    fn pow(&self, rhs: &Self) -> Result<Self> {
+        self.checked_pow(rhs)
+    }
+
+    fn neg(&self) -> Result<Self> {
+        Ok(Rational::neg(self))
+    }
+
+    fn constant(name: &str) -> Result<Self> {
+        if f64::constant(name).is_ok() {
+            bail!(CaculatorError::Inexact(name.to_string()));
+        }
+        bail!(CaculatorError::UnknownIdentifier(name.to_string()))
+    }
+
+    fn call(name: &str, args: &[Self]) -> Result<Self> {
+        let x = unary(name, args)?;
+        let result = match name {
+            "abs" => x.abs(),
+            "floor" => x.floor(),
+            "ceil" => x.ceil(),
+            "numer" => Rational::from_int(x.numer()),
+            "denom" => Rational::from_int(x.denom()),
+            "sqrt" => x.checked_pow(&Rational::new(1, 2)?)?,
+            _ => bail!(CaculatorError::UnknownFunction(name.to_string())),
+        };
+        Ok(result)
+    }
+
+    // exact results stay fractions unless a notation asks for digits
+    fn format(&self, format: &Format) -> String {
+        match format.notation {
+            Notation::Shortest | Notation::Fraction(_) => format.digits(&self.to_string()),
+            Notation::Repeating(_) => format.digits(&self.to_repeating_decimal(format::MAX_PERIOD)),
+            _ => format.real(self.to_f64()),
+        }
+    }
+
+    // long division keeps every digit exact, e.g. 1/3 is 0.1 in base 3
+    fn radix(&self, radix: u32) -> Result<String> {
+        format::check_radix(radix)?;
+        let base = radix as u128;
+        let (num, den) = (self.numer().unsigned_abs(), self.denom() as u128);
+        let digit = |d: u128| {
+            std::char::from_digit(d as u32, radix)
+                .unwrap_or('?')
+                .to_ascii_uppercase()
+        };
+
+        let mut int = num / den;
+        let mut digits = Vec::new();
+        loop {
+            digits.push(digit(int % base));
+            int /= base;
+            if int == 0 {
+                break;
+            }
+        }
+        digits.reverse();
+
+        let mut rem = num % den;
+        if rem != 0 {
+            digits.push('.');
+            for _ in 0..format::fraction_digits(radix) {
+                let Some(r) = rem.checked_mul(base) else {
+                    return format::radix(self.to_f64(), radix);
+                };
+                digits.push(digit(r / den));
+                rem = r % den;
+                if rem == 0 {
+                    break;
+                }
+            }
+        }
+
+        let sign = if self.numer() < 0 { "-" } else { "" };
+        Ok(format!(
+            "{}{}",
+            sign,
+            digits.into_iter().collect::<String>()
+        ))
+    }
+}
+
+// the single argument of a one-argument function
+fn unary<'a, N>(name: &str, args: &'a [N]) -> Result<&'a N> {
+    match args {
+        [x] => Ok(x),
+        _ => bail!(CaculatorError::ArgumentCount(name.to_string(), 1)),
+    }
+}
@@ -0,0 +1,194 @@
+// Number theory on integers. Moduli and the numbers tested for primality
+// fit in a `u64`, so products of two residues fit in a `u128`.
+
+/// True for the functions taking integers only, like `gcd(a, b)`.
+pub fn is_number_theory(name: &str) -> bool {
+    matches!(
+        name,
+        "gcd" | "lcm" | "isprime" | "factor" | "nextprime" | "totient" | "modinv" | "powmod"
+    )
+}
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a as i128
+}
+
+/// The least common multiple, `None` if it overflows.
+pub fn lcm(a: i128, b: i128) -> Option<i128> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b).map(i128::abs)
+}
+
+/// `a mod m` with the sign of `m`, so `-7 mod 3` is `2`.
+pub fn modulo(a: i128, m: i128) -> i128 {
+    let r = a % m;
+    if r != 0 && (r < 0) != (m < 0) {
+        r + m
+    } else {
+        r
+    }
+}
+
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+/// `b^e mod m` by repeated squaring, for `m > 0`.
+pub fn pow_mod(b: i128, mut e: u64, m: u64) -> u64 {
+    let mut base = modulo(b, m as i128) as u64;
+    let mut acc = 1 % m;
+    while e > 0 {
+        if e & 1 == 1 {
+            acc = mul_mod(acc, base, m);
+        }
+        base = mul_mod(base, base, m);
+        e >>= 1;
+    }
+    acc
+}
+
+/// The `x` in `0..m` with `a*x = 1 mod m`, `None` unless `a` and `m` are coprime.
+pub fn mod_inv(a: i128, m: u64) -> Option<u64> {
+    // extended Euclid, tracking only the coefficient of `a`
+    let (mut r0, mut r1) = (m as i128, modulo(a, m as i128));
+    let (mut t0, mut t1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (t0, t1) = (t1, t0 - q * t1);
+    }
+    (r0 == 1).then(|| modulo(t0, m as i128) as u64)
+}
+
+/// Deterministic Miller-Rabin: these bases decide every `u64`.
+pub fn is_prime(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    for p in BASES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    BASES.iter().all(|&a| {
+        let mut x = pow_mod(a as i128, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        (1..s).any(|_| {
+            x = mul_mod(x, x, n);
+            x == n - 1
+        })
+    })
+}
+
+/// The smallest prime above `n`, `None` past the largest `u64` prime.
+pub fn next_prime(n: u64) -> Option<u64> {
+    (n.checked_add(1)?..=u64::MAX).find(|&k| is_prime(k))
+}
+
+/// The prime factors of `n` with their exponents, smallest first.
+pub fn factor(n: u64) -> Vec<(u64, u32)> {
+    let mut primes = Vec::new();
+    split(n, &mut primes);
+    primes.sort_unstable();
+    let mut factors: Vec<(u64, u32)> = Vec::new();
+    for p in primes {
+        match factors.last_mut() {
+            Some((q, e)) if *q == p => *e += 1,
+            _ => factors.push((p, 1)),
+        }
+    }
+    factors
+}
+
+// pushes the prime factors of `n`: small ones by trial division, the rest
+// split by Pollard's rho
+fn split(mut n: u64, primes: &mut Vec<u64>) {
+    for p in [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        while n.is_multiple_of(p) {
+            primes.push(p);
+            n /= p;
+        }
+    }
+    if n == 1 {
+        return;
+    }
+    if is_prime(n) {
+        primes.push(n);
+        return;
+    }
+    let d = (1..).find_map(|c| rho(n, c)).unwrap_or(n);
+    split(d, primes);
+    split(n / d, primes);
+}
+
+// a proper divisor of the composite `n` from `x^2 + c`, if this `c` finds one
+fn rho(n: u64, c: u64) -> Option<u64> {
+    let f = |x: u64| ((mul_mod(x, x, n) as u128 + c as u128) % n as u128) as u64;
+    let (mut x, mut y, mut d) = (2, 2, 1);
+    while d == 1 {
+        x = f(x);
+        y = f(f(y));
+        d = gcd(x.abs_diff(y) as i128, n as i128) as u64;
+    }
+    (d != n).then_some(d)
+}
+
+/// Euler's totient: how many of `1..=n` are coprime to `n`.
+pub fn totient(n: u64) -> u64 {
+    factor(n)
+        .into_iter()
+        .fold(n, |phi, (p, _)| phi / p * (p - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_divisibility() {
+        assert_eq!(gcd(12, -18), 6);
+        assert_eq!(gcd(0, 5), 5);
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(i128::MAX, 2), None);
+        assert_eq!(modulo(-7, 3), 2);
+        assert_eq!(modulo(7, -3), -2);
+        assert_eq!(pow_mod(2, 10, 1000), 24);
+        assert_eq!(pow_mod(-2, 3, 5), 2);
+        assert_eq!(pow_mod(5, 0, 1), 0);
+        assert_eq!(mod_inv(3, 11), Some(4));
+        assert_eq!(mod_inv(-3, 11), Some(7));
+        assert_eq!(mod_inv(6, 9), None);
+    }
+
+    #[test]
+    fn test_primes() {
+        let small: Vec<u64> = (0..30).filter(|&n| is_prime(n)).collect();
+        assert_eq!(small, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        // a strong pseudoprime to base 2, and the largest u64 prime
+        assert!(!is_prime(2047));
+        assert!(is_prime(18446744073709551557));
+        assert_eq!(next_prime(13), Some(17));
+        assert_eq!(next_prime(18446744073709551557), None);
+        assert_eq!(factor(360), vec![(2, 3), (3, 2), (5, 1)]);
+        // two large primes only Pollard's rho splits
+        assert_eq!(
+            factor(1000000007 * 998244353),
+            vec![(998244353, 1), (1000000007, 1)]
+        );
+        assert_eq!(factor(1), vec![]);
+        assert_eq!(totient(36), 12);
+        assert_eq!(totient(97), 96);
+        assert_eq!(totient(1), 1);
+    }
+}
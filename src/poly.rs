@@ -0,0 +1,241 @@
+use anyhow::{bail, Result};
+
+use crate::complex::Complex;
+use crate::errors::CaculatorError;
+use crate::format::Format;
+use crate::matrix::Matrix;
+use crate::number::Number;
+
+// Polynomials are slices of coefficients with the highest power first, so
+// `[1, 0, -4]` is `x^2 - 4`.
+
+const MAX_ITERATIONS: usize = 1000;
+
+/// The number of arguments a polynomial function takes, `None` for other names.
+pub fn arity(name: &str) -> Option<usize> {
+    match name {
+        "polyroots" => Some(1),
+        "polyval" | "polymul" | "polydiv" | "polyrem" => Some(2),
+        "polyfit" => Some(3),
+        _ => None,
+    }
+}
+
+// drops leading zero coefficients, keeping at least one
+fn trim<N: Number>(p: &[N]) -> &[N] {
+    let start = p
+        .iter()
+        .position(|c| !c.is_zero())
+        .unwrap_or(p.len().saturating_sub(1));
+    &p[start..]
+}
+
+/// Evaluates `p` at `x` by Horner's rule.
+pub fn eval<N: Number>(p: &[N], x: &N) -> Result<N> {
+    let mut acc = N::parse("0")?;
+    for c in p {
+        acc = acc.mul(x)?.add(c)?;
+    }
+    Ok(acc)
+}
+
+pub fn mul<N: Number>(p: &[N], q: &[N]) -> Result<Vec<N>> {
+    let (p, q) = (trim(p), trim(q));
+    let mut product = vec![N::parse("0")?; p.len() + q.len() - 1];
+    for (i, a) in p.iter().enumerate() {
+        for (j, b) in q.iter().enumerate() {
+            product[i + j] = product[i + j].add(&a.mul(b)?)?;
+        }
+    }
+    Ok(product)
+}
+
+/// Long division, returning the quotient and the remainder.
+pub fn div<N: Number>(p: &[N], q: &[N]) -> Result<(Vec<N>, Vec<N>)> {
+    let (p, q) = (trim(p), trim(q));
+    if q[0].is_zero() {
+        bail!(CaculatorError::DivideByZero);
+    }
+    if p.len() < q.len() {
+        return Ok((vec![N::parse("0")?], p.to_vec()));
+    }
+
+    let mut rem = p.to_vec();
+    let mut quot = Vec::with_capacity(p.len() - q.len() + 1);
+    for i in 0..=p.len() - q.len() {
+        let k = rem[i].div(&q[0])?;
+        for (j, c) in q.iter().enumerate() {
+            rem[i + j] = rem[i + j].sub(&k.mul(c)?)?;
+        }
+        quot.push(k);
+    }
+    let rem = trim(&rem[p.len() - q.len() + 1..]).to_vec();
+    if rem.is_empty() {
+        return Ok((quot, vec![N::parse("0")?]));
+    }
+    Ok((quot, rem))
+}
+
+/// The least-squares polynomial of `degree` through the points, found from
+/// the normal equations so exact backends give exact coefficients.
+pub fn fit<N: Number>(xs: &[N], ys: &[N], degree: usize) -> Result<Vec<N>> {
+    if xs.len() != ys.len() {
+        bail!(CaculatorError::ShapeMismatch(
+            format!("[{}]", xs.len()),
+            format!("[{}]", ys.len())
+        ));
+    }
+    // one row of powers per point, the highest first
+    let mut data = Vec::with_capacity(xs.len() * (degree + 1));
+    for x in xs {
+        let mut row = vec![N::parse("1")?];
+        for _ in 0..degree {
+            let next = row[0].mul(x)?;
+            row.insert(0, next);
+        }
+        data.extend(row);
+    }
+    let v = Matrix::new(xs.len(), degree + 1, data)?;
+    let vt = v.transpose()?;
+    let y = Matrix::vector(ys.to_vec())?;
+    Ok(vt.mul(&v)?.solve(&vt.mul(&y)?)?.data)
+}
+
+/// All complex roots by the Durand-Kerner iteration, polished with Newton
+/// steps on `p` itself and sorted by real part.
+pub fn roots(p: &[f64]) -> Result<Vec<Complex>> {
+    let start = p.iter().position(|&c| c != 0.0).unwrap_or(p.len());
+    let p = &p[start..];
+    if p.len() < 2 {
+        let format = Format::default();
+        let coeffs: Vec<String> = p.iter().map(|&c| format.real(c)).collect();
+        bail!(CaculatorError::NoRoot(format!("[{}]", coeffs.join(", "))));
+    }
+    let eval = |z: Complex| {
+        p.iter()
+            .fold(Complex::default(), |acc, &c| acc * z + Complex::from(c))
+    };
+
+    // roots at zero are exact, the rest come from the monic remainder
+    let zeros = p.iter().rev().take_while(|&&c| c == 0.0).count();
+    let monic: Vec<Complex> = p[..p.len() - zeros]
+        .iter()
+        .map(|&c| Complex::from(c / p[0]))
+        .collect();
+    let n = monic.len() - 1;
+    let seed = Complex::new(0.4, 0.9);
+    let mut z: Vec<Complex> = (0..n).map(|k| seed.pow(Complex::from(k as f64))).collect();
+    for _ in 0..MAX_ITERATIONS {
+        let mut change: f64 = 0.0;
+        for k in 0..n {
+            let value = monic
+                .iter()
+                .fold(Complex::default(), |acc, &c| acc * z[k] + c);
+            let mut denom = Complex::from(1.0);
+            for j in (0..n).filter(|&j| j != k) {
+                denom = denom * (z[k] - z[j]);
+            }
+            let step = value / denom;
+            if step.abs().is_finite() {
+                z[k] = z[k] - step;
+                change = change.max(step.abs() / z[k].abs().max(1.0));
+            }
+        }
+        if change < 1e-15 {
+            break;
+        }
+    }
+
+    let slope = |z: Complex| {
+        let degree = p.len() - 1;
+        p[..degree]
+            .iter()
+            .enumerate()
+            .fold(Complex::default(), |acc, (i, &c)| {
+                acc * z + Complex::from(c * (degree - i) as f64)
+            })
+    };
+    let mut roots: Vec<Complex> = z
+        .into_iter()
+        .map(|mut z| {
+            for _ in 0..3 {
+                let step = eval(z) / slope(z);
+                if !step.abs().is_finite() {
+                    break;
+                }
+                z = z - step;
+            }
+            polish(&eval, z)
+        })
+        .chain(std::iter::repeat_n(Complex::default(), zeros))
+        .collect();
+    roots.sort_by(|a, b| a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im)));
+    Ok(roots)
+}
+
+// drops imaginary noise and prefers short decimals that are as good a root
+fn polish(eval: &impl Fn(Complex) -> Complex, z: Complex) -> Complex {
+    let z = match z.im.abs() <= 1e-9 * z.abs().max(1.0) {
+        true => Complex::from(z.re),
+        false => z,
+    };
+    let short = |x: f64| format!("{:.11e}", x).parse().unwrap_or(x);
+    let rounded = Complex::new(short(z.re), short(z.im));
+    if eval(rounded).abs() <= eval(z).abs() {
+        rounded
+    } else {
+        z
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rational::Rational;
+
+    fn rationals(xs: &[i128]) -> Vec<Rational> {
+        xs.iter().map(|&x| Rational::from_int(x)).collect()
+    }
+
+    #[test]
+    fn test_arithmetic() -> anyhow::Result<()> {
+        let p = rationals(&[1, -3, 2]);
+        assert_eq!(eval(&p, &Rational::from_int(3))?, Rational::from_int(2));
+        let product = mul(&p, &rationals(&[1, 1]))?;
+        assert_eq!(product, rationals(&[1, -2, -1, 2]));
+        let (quot, rem) = div(&product, &rationals(&[1, -1]))?;
+        assert_eq!((quot, rem), (rationals(&[1, -1, -2]), rationals(&[0])));
+        let (quot, rem) = div(&rationals(&[1, 0, 1]), &rationals(&[2, 0]))?;
+        assert_eq!(quot[0].to_string(), "1/2");
+        assert_eq!(rem, rationals(&[1]));
+        assert!(div(&p, &rationals(&[0])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fit() -> anyhow::Result<()> {
+        // exactly on 2x^2 + 1
+        let xs = rationals(&[-1, 0, 1, 2]);
+        let ys = rationals(&[3, 1, 3, 9]);
+        assert_eq!(fit(&xs, &ys, 2)?, rationals(&[2, 0, 1]));
+        let line = fit(&[0.0, 1.0, 2.0], &[1.0, 2.0, 2.0], 1)?;
+        assert!((line[0] - 0.5).abs() < 1e-15 && (line[1] - 7.0 / 6.0).abs() < 1e-15);
+        Ok(())
+    }
+
+    #[test]
+    fn test_roots() -> anyhow::Result<()> {
+        let r = roots(&[1.0, -3.0, 2.0])?;
+        assert_eq!(r, vec![Complex::from(1.0), Complex::from(2.0)]);
+        let r = roots(&[1.0, 0.0, 1.0])?;
+        assert_eq!(r, vec![Complex::new(0.0, -1.0), Complex::new(0.0, 1.0)]);
+        let r = roots(&[1.0, 0.0, -1.0, 0.0])?;
+        assert_eq!(
+            r,
+            vec![Complex::from(-1.0), Complex::from(0.0), Complex::from(1.0)]
+        );
+        assert_eq!(roots(&[1.0, -6.0, 11.0, -6.0])?.len(), 3);
+        assert!(roots(&[5.0]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// True for the functions drawing random numbers. They give a new value on
+/// every call, so they are evaluated where they occur and never folded.
+pub fn is_random(name: &str) -> bool {
+    matches!(name, "rand" | "randint" | "randn" | "choice")
+}
+
+/// A small SplitMix64 generator: the same seed gives the same numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// A generator seeded from the clock, different on every run.
+    pub fn from_clock() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `0..n`, for `n > 0`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    /// Uniform over the integers from `a` to `b`, both included.
+    pub fn int(&mut self, a: i64, b: i64) -> i64 {
+        let span = (b as i128 - a as i128 + 1) as u128;
+        if span > u64::MAX as u128 {
+            return self.next_u64() as i64;
+        }
+        (a as i128 + self.below(span as u64) as i128) as i64
+    }
+
+    /// Normally distributed with mean `mu` and standard deviation `sigma`,
+    /// by the Box-Muller transform.
+    pub fn normal(&mut self, mu: f64, sigma: f64) -> f64 {
+        // 1 - u is never 0, so its logarithm is finite
+        let (u, v) = (1.0 - self.uniform(), self.uniform());
+        mu + sigma * (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let (mut a, mut b) = (Rng::new(42), Rng::new(42));
+        let xs: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        let ys: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+        assert_eq!(xs, ys);
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            let u = rng.uniform();
+            assert!((0.0..1.0).contains(&u));
+            assert!((-3..=3).contains(&rng.int(-3, 3)));
+        }
+        assert_eq!(rng.int(5, 5), 5);
+        // every value comes up
+        let mut seen = [false; 6];
+        for _ in 0..200 {
+            seen[rng.int(1, 6) as usize - 1] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+
+        let n = 20000;
+        let xs: Vec<f64> = (0..n).map(|_| rng.normal(10.0, 2.0)).collect();
+        let mean = xs.iter().sum::<f64>() / n as f64;
+        let var = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        assert!((mean - 10.0).abs() < 0.1 && (var.sqrt() - 2.0).abs() < 0.1);
+    }
+}
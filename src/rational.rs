@@ -0,0 +1,411 @@
+use anyhow::{bail, Result};
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::errors::CaculatorError;
+use crate::format::Format;
+
+/// An exact fraction `num / den`, always kept in lowest terms with `den > 0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    pub fn new(num: i128, den: i128) -> Result<Self> {
+        if den == 0 {
+            bail!(CaculatorError::DivideByZero);
+        }
+        let g = gcd(num, den);
+        let (mut num, mut den) = (num / g, den / g);
+        if den < 0 {
+            let describe = move || format!("{}/{}", num, den);
+            num = checked(num.checked_neg(), describe)?;
+            den = checked(den.checked_neg(), describe)?;
+        }
+        Ok(Self { num, den })
+    }
+
+    pub fn from_int(num: i128) -> Self {
+        Self { num, den: 1 }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.den
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /// Parses a decimal literal such as `1.25` exactly, as `5/4`.
+    pub fn parse(literal: &str) -> Result<Self> {
+        // `1.5e-3` is `15/10000`
+        if let Some((mantissa, exp)) = literal.split_once(['e', 'E']) {
+            let Ok(exp) = exp.parse::<i32>() else {
+                bail!(CaculatorError::InvalidExpression(literal.to_string()));
+            };
+            let power = checked(10i128.checked_pow(exp.unsigned_abs()), || {
+                literal.to_string()
+            })?;
+            let scale = match exp < 0 {
+                true => Self { num: 1, den: power },
+                false => Self { num: power, den: 1 },
+            };
+            return Self::parse(mantissa)?.product(&scale, || literal.to_string());
+        }
+        let (int, frac) = literal.split_once('.').unwrap_or((literal, ""));
+        let mut num: i128 = 0;
+        let mut den: i128 = 1;
+        for ch in int.chars().chain(frac.chars()) {
+            let Some(digit) = ch.to_digit(10) else {
+                bail!(CaculatorError::InvalidExpression(literal.to_string()));
+            };
+            num = checked(num.checked_mul(10), || literal.to_string())?;
+            num = checked(num.checked_add(digit as i128), || literal.to_string())?;
+        }
+        for _ in frac.chars() {
+            den = checked(den.checked_mul(10), || literal.to_string())?;
+        }
+        Self::new(num, den)
+    }
+
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self> {
+        self.sum(rhs, || format!("{} + {}", self, rhs))
+    }
+
+    pub fn checked_sub(&self, rhs: &Self) -> Result<Self> {
+        self.sum(&rhs.neg(), || format!("{} - {}", self, rhs))
+    }
+
+    // `describe` writes the operation for the overflow error
+    fn sum(&self, rhs: &Self, describe: impl Fn() -> String) -> Result<Self> {
+        let a = checked(self.num.checked_mul(rhs.den), &describe)?;
+        let b = checked(rhs.num.checked_mul(self.den), &describe)?;
+        let den = checked(self.den.checked_mul(rhs.den), &describe)?;
+        Self::new(checked(a.checked_add(b), &describe)?, den)
+    }
+
+    pub fn checked_mul(&self, rhs: &Self) -> Result<Self> {
+        self.product(rhs, || format!("{} * {}", self, rhs))
+    }
+
+    pub fn checked_div(&self, rhs: &Self) -> Result<Self> {
+        if rhs.num == 0 {
+            bail!(CaculatorError::DivideByZero);
+        }
+        let reciprocal = Self {
+            num: rhs.den,
+            den: rhs.num,
+        };
+        self.product(&reciprocal, || format!("{} / {}", self, rhs))
+    }
+
+    fn product(&self, rhs: &Self, describe: impl Fn() -> String) -> Result<Self> {
+        // cross-reduce first to keep the intermediate products small
+        let g1 = gcd(self.num, rhs.den);
+        let g2 = gcd(rhs.num, self.den);
+        let num = checked((self.num / g1).checked_mul(rhs.num / g2), &describe)?;
+        let den = checked((self.den / g2).checked_mul(rhs.den / g1), &describe)?;
+        Self::new(num, den)
+    }
+
+    /// Raises to a rational power, failing if the result is irrational.
+    pub fn checked_pow(&self, exp: &Self) -> Result<Self> {
+        let root = if exp.den == 1 {
+            *self
+        } else {
+            let Ok(root) = u32::try_from(exp.den) else {
+                bail!(CaculatorError::Inexact(format!("{}^{}", self, exp)));
+            };
+            match (nth_root(self.num, root), nth_root(self.den, root)) {
+                (Some(num), Some(den)) => Self::new(num, den)?,
+                _ => bail!(CaculatorError::Inexact(format!("{}^{}", self, exp))),
+            }
+        };
+
+        let describe = || format!("{}^{}", self, exp);
+        let mut base = root;
+        let mut acc = Self::from_int(1);
+        let mut k = exp.num.unsigned_abs();
+        while k > 0 {
+            if k & 1 == 1 {
+                acc = acc.product(&base, describe)?;
+            }
+            k >>= 1;
+            if k > 0 {
+                base = base.product(&base, describe)?;
+            }
+        }
+        if exp.num < 0 {
+            return Self::from_int(1).checked_div(&acc);
+        }
+        Ok(acc)
+    }
+
+    pub fn neg(&self) -> Self {
+        Self {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+
+    pub fn abs(&self) -> Self {
+        Self {
+            num: self.num.abs(),
+            den: self.den,
+        }
+    }
+
+    /// The first convergent of the continued fraction of `x` that is within
+    /// `tolerance` of it: `0.333333` is `1/3`.
+    pub fn approximate(x: f64, tolerance: f64) -> Result<Self> {
+        if !x.is_finite() {
+            bail!(CaculatorError::Overflow {
+                op: x.to_string(),
+                span: None,
+            });
+        }
+        // convergents h/k from the recurrence h(n) = a(n) h(n-1) + h(n-2)
+        let (mut h, mut h_prev) = (1i128, 0i128);
+        let (mut k, mut k_prev) = (0i128, 1i128);
+        let mut rest = x;
+        for _ in 0..64 {
+            let a = rest.floor();
+            if a.abs() >= i64::MAX as f64 {
+                break;
+            }
+            let a = a as i128;
+            let next = |x: i128, prev: i128| a.checked_mul(x)?.checked_add(prev);
+            (h, h_prev) = (checked(next(h, h_prev), || x.to_string())?, h);
+            (k, k_prev) = (checked(next(k, k_prev), || x.to_string())?, k);
+            if (x - h as f64 / k as f64).abs() <= tolerance || rest == a as f64 {
+                return Self::new(h, k);
+            }
+            rest = 1.0 / (rest - a as f64);
+        }
+        bail!(CaculatorError::Inexact(x.to_string()))
+    }
+
+    /// Writes the decimal expansion with the repeating part in parentheses,
+    /// e.g. `0.(142857)` for 1/7. Periods longer than `max_digits` are cut off with `…`.
+    pub fn to_repeating_decimal(&self, max_digits: usize) -> String {
+        let sign = if self.num < 0 { "-" } else { "" };
+        let (num, den) = (self.num.unsigned_abs(), self.den as u128);
+        let int = num / den;
+        let mut rem = num % den;
+        if rem == 0 {
+            return format!("{}{}", sign, int);
+        }
+
+        // a remainder seen before starts the period over again
+        let mut seen = Vec::new();
+        let mut digits = String::new();
+        while rem != 0 && digits.len() < max_digits {
+            if let Some(start) = seen.iter().position(|&r| r == rem) {
+                return format!("{}{}.{}({})", sign, int, &digits[..start], &digits[start..]);
+            }
+            seen.push(rem);
+            // remainders of denominators near `i128::MAX` leave no room for a digit
+            let Some(r) = rem.checked_mul(10) else {
+                return Format::default().real(self.to_f64());
+            };
+            digits.push(char::from(b'0' + (r / den) as u8));
+            rem = r % den;
+        }
+        if rem != 0 {
+            digits.push('…');
+        }
+        format!("{}{}.{}", sign, int, digits)
+    }
+
+    pub fn floor(&self) -> Self {
+        Self::from_int(self.num.div_euclid(self.den))
+    }
+
+    pub fn ceil(&self) -> Self {
+        Self::from_int(-(-self.num).div_euclid(self.den))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (
+            self.num.checked_mul(other.den),
+            other.num.checked_mul(self.den),
+        ) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => cmp_fractions((self.num, self.den), (other.num, other.den)),
+        }
+    }
+}
+
+// compares `a.0 / a.1` and `b.0 / b.1` by their continued fractions, which
+// never leave `i128`
+fn cmp_fractions(mut a: (i128, i128), mut b: (i128, i128)) -> Ordering {
+    loop {
+        let (qa, qb) = (a.0.div_euclid(a.1), b.0.div_euclid(b.1));
+        if qa != qb {
+            return qa.cmp(&qb);
+        }
+        let (ra, rb) = (a.0.rem_euclid(a.1), b.0.rem_euclid(b.1));
+        match (ra, rb) {
+            (0, 0) => return Ordering::Equal,
+            (0, _) => return Ordering::Less,
+            (_, 0) => return Ordering::Greater,
+            // the larger fraction has the smaller reciprocal
+            _ => (a, b) = ((b.1, rb), (a.1, ra)),
+        }
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    if a == 0 {
+        return 1;
+    }
+    a as i128
+}
+
+fn checked(v: Option<i128>, describe: impl Fn() -> String) -> Result<i128> {
+    match v {
+        Some(v) => Ok(v),
+        None => bail!(CaculatorError::Overflow {
+            op: describe(),
+            span: None,
+        }),
+    }
+}
+
+// exact integer n-th root, if there is one
+fn nth_root(v: i128, n: u32) -> Option<i128> {
+    if v < 0 && n.is_multiple_of(2) {
+        return None;
+    }
+    let guess = (v.unsigned_abs() as f64).powf(1.0 / n as f64).round() as i128;
+    let root = if v < 0 { -guess } else { guess };
+    [root - 1, root, root + 1]
+        .into_iter()
+        .find(|candidate| candidate.checked_pow(n) == Some(v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() -> anyhow::Result<()> {
+        assert_eq!(Rational::parse("1.25")?, Rational::new(5, 4)?);
+        assert_eq!(Rational::parse("0.1")?.to_string(), "1/10");
+        assert_eq!(Rational::parse("42")?.to_string(), "42");
+        assert_eq!(Rational::parse("1.5e3")?.to_string(), "1500");
+        assert_eq!(Rational::parse("25e-3")?.to_string(), "1/40");
+        assert!(Rational::parse("1e40").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_arithmetic() -> anyhow::Result<()> {
+        let third = Rational::new(1, 3)?;
+        let sixth = Rational::new(-1, -6)?;
+        assert_eq!(third.checked_add(&sixth)?, Rational::new(1, 2)?);
+        assert_eq!(third.checked_sub(&third)?, Rational::from_int(0));
+        assert_eq!(third.checked_div(&sixth)?, Rational::from_int(2));
+        assert_eq!(
+            Rational::new(4, 9)?.checked_pow(&Rational::new(-3, 2)?)?,
+            Rational::new(27, 8)?
+        );
+        assert!(Rational::from_int(2)
+            .checked_pow(&Rational::new(1, 2)?)
+            .is_err());
+        assert!(third.checked_div(&Rational::from_int(0)).is_err());
+        // overflow names the operation that caused it
+        let big = Rational::new(1, i128::MAX)?;
+        let err = big.checked_div(&Rational::from_int(3)).unwrap_err();
+        let expected = format!("number overflow in: 1/{} / 3", i128::MAX);
+        assert_eq!(err.to_string(), expected);
+        let err = Rational::from_int(10).checked_pow(&Rational::from_int(40));
+        assert_eq!(err.unwrap_err().to_string(), "number overflow in: 10^40");
+        Ok(())
+    }
+
+    #[test]
+    fn test_ordering() -> anyhow::Result<()> {
+        assert!(Rational::new(1, 3)? < Rational::new(1, 2)?);
+        assert!(Rational::new(-1, 2)? < Rational::new(-1, 3)?);
+        // cross products beyond `i128`, and values `f64` cannot tell apart
+        let big = 10i128.pow(20);
+        let (a, b) = (Rational::new(big + 1, big)?, Rational::new(big + 2, big)?);
+        assert!(a < b && a.to_f64() == b.to_f64());
+        let (c, d) = (
+            Rational::new(-big - 1, big - 1)?,
+            Rational::new(-big, big - 3)?,
+        );
+        assert_eq!(c.cmp(&d), Ordering::Greater);
+        assert_eq!(a.cmp(&a), Ordering::Equal);
+        Ok(())
+    }
+
+    #[test]
+    fn test_approximate() -> anyhow::Result<()> {
+        let t_cases = vec![
+            (0.333333, 1e-6, "1/3"),
+            (1.2499999999999998, 1e-9, "5/4"),
+            (-0.75, 1e-9, "-3/4"),
+            (std::f64::consts::PI, 2e-3, "22/7"),
+            (std::f64::consts::PI, 1e-6, "355/113"),
+            (42.0, 1e-9, "42"),
+        ];
+        for tc in t_cases {
+            assert_eq!(Rational::approximate(tc.0, tc.1)?.to_string(), tc.2);
+        }
+        assert!(Rational::approximate(f64::NAN, 1e-9).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_repeating_decimal() -> anyhow::Result<()> {
+        let t_cases = vec![
+            (Rational::new(1, 7)?, "0.(142857)"),
+            (Rational::new(5, 4)?, "1.25"),
+            (Rational::new(-1, 6)?, "-0.1(6)"),
+            (Rational::new(22, 3)?, "7.(3)"),
+            (Rational::from_int(3), "3"),
+        ];
+        for tc in t_cases {
+            assert_eq!(tc.0.to_repeating_decimal(64), tc.1);
+        }
+        assert_eq!(Rational::new(1, 97)?.to_repeating_decimal(4), "0.0103…");
+        let tiny = Rational::new(1, 10i128.pow(38) + 1)?;
+        assert_eq!(tiny.to_repeating_decimal(64), "1e-38");
+        Ok(())
+    }
+}
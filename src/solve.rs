@@ -0,0 +1,164 @@
+// Numeric root finding on real functions. Points where the function has no
+// real value are passed in as `NaN` and treated as gaps.
+
+const MAX_NEWTON: usize = 60;
+const MAX_BISECT: usize = 200;
+// a bracket is searched for by doubling the step this many times
+const MAX_EXPAND: usize = 80;
+// subintervals scanned for sign changes by `roots`
+const SAMPLES: usize = 2000;
+
+/// A root of `f` near `guess`: Newton's method with a numeric derivative,
+/// falling back to bisecting a bracket found by widening steps around the guess.
+pub fn root(f: impl Fn(f64) -> f64, guess: f64) -> Option<f64> {
+    newton(&f, guess)
+        .or_else(|| {
+            let (a, b) = bracket(&f, guess)?;
+            bisect(&f, a, b)
+        })
+        .map(|x| snap(&f, x))
+}
+
+/// All roots of `f` in `[a, b]` where it changes sign, in increasing order.
+/// Roots where the graph only touches zero are found if a sample hits them.
+pub fn roots(f: impl Fn(f64) -> f64, a: f64, b: f64) -> Vec<f64> {
+    let (a, b) = (a.min(b), a.max(b));
+    let step = (b - a) / SAMPLES as f64;
+    let mut found: Vec<f64> = Vec::new();
+    let mut push = |x: f64| {
+        let x = snap(&f, x);
+        let tolerance = 1e-9 * x.abs().max(1.0);
+        if found.last().is_none_or(|last| (x - last).abs() > tolerance) {
+            found.push(x);
+        }
+    };
+
+    let mut x0 = a;
+    let mut y0 = f(a);
+    for i in 1..=SAMPLES {
+        let x1 = if i == SAMPLES { b } else { a + step * i as f64 };
+        let y1 = f(x1);
+        if y0 == 0.0 {
+            push(x0);
+        } else if y0.is_finite() && y1.is_finite() && y0.signum() != y1.signum() && y1 != 0.0 {
+            // a sign change across a pole is not a root
+            if let Some(x) = bisect(&f, x0, x1).filter(|&x| f(x).abs() <= y0.abs().max(y1.abs())) {
+                push(x);
+            }
+        }
+        (x0, y0) = (x1, y1);
+    }
+    if y0 == 0.0 {
+        push(x0);
+    }
+    found
+}
+
+fn newton(f: &impl Fn(f64) -> f64, guess: f64) -> Option<f64> {
+    let mut x = guess;
+    for _ in 0..MAX_NEWTON {
+        let y = f(x);
+        if y == 0.0 {
+            return Some(x);
+        }
+        let h = 1e-7 * x.abs().max(1.0);
+        let slope = (f(x + h) - f(x - h)) / (2.0 * h);
+        if !y.is_finite() || !slope.is_finite() || slope == 0.0 {
+            return None;
+        }
+        let next = x - y / slope;
+        if (next - x).abs() <= 1e-14 * x.abs().max(1.0) {
+            return Some(next);
+        }
+        x = next;
+    }
+    // accept a slow finish if the function is small there
+    let y = f(x);
+    (y.abs() < 1e-10).then_some(x)
+}
+
+// widens around `guess` until the function changes sign
+fn bracket(f: &impl Fn(f64) -> f64, guess: f64) -> Option<(f64, f64)> {
+    let y = f(guess);
+    let mut step = 0.01 * guess.abs().max(1.0);
+    for _ in 0..MAX_EXPAND {
+        for x in [guess - step, guess + step] {
+            let fx = f(x);
+            if fx == 0.0 {
+                return Some((x, x));
+            }
+            if y.is_finite() && fx.is_finite() && y.signum() != fx.signum() {
+                return Some((guess.min(x), guess.max(x)));
+            }
+        }
+        step *= 2.0;
+    }
+    None
+}
+
+// halves a bracket with a sign change down to adjacent floats
+fn bisect(f: &impl Fn(f64) -> f64, mut a: f64, mut b: f64) -> Option<f64> {
+    let mut fa = f(a);
+    if fa == 0.0 {
+        return Some(a);
+    }
+    for _ in 0..MAX_BISECT {
+        let mid = a + (b - a) / 2.0;
+        if mid <= a || mid >= b {
+            break;
+        }
+        let fm = f(mid);
+        if fm == 0.0 {
+            return Some(mid);
+        }
+        if !fm.is_finite() {
+            return None;
+        }
+        if fm.signum() == fa.signum() {
+            (a, fa) = (mid, fm);
+        } else {
+            b = mid;
+        }
+    }
+    Some(a + (b - a) / 2.0)
+}
+
+// prefers a nearby short decimal, `2` over `1.9999999999999998`, if it is
+// at least as good a root
+fn snap(f: &impl Fn(f64) -> f64, x: f64) -> f64 {
+    let short: f64 = format!("{:.11e}", x).parse().unwrap_or(x);
+    if f(short).abs() <= f(x).abs() {
+        short
+    } else {
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root() {
+        assert_eq!(root(|x| x * x - 4.0, 1.0), Some(2.0));
+        let r = root(|x| x.cos() - x, 1.0).unwrap();
+        assert!((r - 0.7390851332151607).abs() < 1e-15);
+        // Newton's method stalls at a flat guess, the bracket search does not
+        assert_eq!(root(|x| x * x - 4.0, 0.0).map(f64::abs), Some(2.0));
+        // defined only for x > 0
+        let r = root(|x| x.ln() - 1.0, 0.5).unwrap();
+        assert!((r - std::f64::consts::E).abs() < 1e-14);
+        assert_eq!(root(|x| x * x + 1.0, 1.0), None);
+    }
+
+    #[test]
+    fn test_roots() {
+        assert_eq!(roots(|x| x * x - 4.0, -5.0, 5.0), vec![-2.0, 2.0]);
+        assert_eq!(roots(|x| x * (x - 1.0), 0.0, 1.0), vec![0.0, 1.0]);
+        let r = roots(f64::sin, 1.0, 10.0);
+        assert_eq!(r.len(), 3);
+        assert!((r[2] - 3.0 * std::f64::consts::PI).abs() < 1e-12);
+        // the sign change of 1/x at 0 is a pole
+        assert!(roots(|x| 1.0 / x, -1.0, 1.0).is_empty());
+    }
+}
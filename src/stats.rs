@@ -0,0 +1,196 @@
+use anyhow::{bail, Result};
+use std::cmp::Ordering;
+
+use crate::errors::CaculatorError;
+use crate::number::Number;
+
+/// Functions taking a whole list of numbers, e.g. `mean([1, 2, 3])`.
+pub fn is_aggregate(name: &str) -> bool {
+    matches!(
+        name,
+        "sum"
+            | "mean"
+            | "median"
+            | "mode"
+            | "stdev"
+            | "variance"
+            | "percentile"
+            | "min"
+            | "max"
+            | "count"
+    )
+}
+
+/// Applies an aggregate function to `xs`; `percentile` also takes the
+/// percentage `p` from 0 to 100.
+pub fn aggregate<N: Number>(name: &str, xs: &[N], p: Option<&N>) -> Result<N> {
+    if xs.is_empty() {
+        bail!(CaculatorError::ArgumentCount(name.to_string(), 1));
+    }
+    match (name, p) {
+        ("percentile", Some(p)) => percentile(xs, p),
+        ("percentile", None) => bail!(CaculatorError::ArgumentCount(name.to_string(), 2)),
+        (_, Some(_)) => bail!(CaculatorError::ArgumentCount(name.to_string(), 1)),
+        ("count", None) => N::parse(&xs.len().to_string()),
+        ("sum", None) => sum(xs),
+        ("mean", None) => mean(xs),
+        ("median", None) => percentile(xs, &N::parse("50")?),
+        ("mode", None) => mode(xs),
+        ("variance", None) => variance(xs),
+        ("stdev", None) => N::call("sqrt", &[variance(xs)?]),
+        ("min", None) => Ok(sorted(xs)?.swap_remove(0)),
+        ("max", None) => Ok(sorted(xs)?.pop().unwrap()),
+        _ => bail!(CaculatorError::UnknownFunction(name.to_string())),
+    }
+}
+
+fn sum<N: Number>(xs: &[N]) -> Result<N> {
+    let mut acc = N::parse("0")?;
+    for x in xs {
+        acc = acc.add(x)?;
+    }
+    Ok(acc)
+}
+
+fn mean<N: Number>(xs: &[N]) -> Result<N> {
+    sum(xs)?.div(&N::parse(&xs.len().to_string())?)
+}
+
+/// The sample variance, dividing by `n - 1`.
+fn variance<N: Number>(xs: &[N]) -> Result<N> {
+    if xs.len() < 2 {
+        bail!(CaculatorError::ArgumentCount("variance".to_string(), 2));
+    }
+    let m = mean(xs)?;
+    let mut acc = N::parse("0")?;
+    for x in xs {
+        let d = x.sub(&m)?;
+        acc = acc.add(&d.mul(&d)?)?;
+    }
+    acc.div(&N::parse(&(xs.len() - 1).to_string())?)
+}
+
+// ordering needs real numbers
+fn sorted<N: Number>(xs: &[N]) -> Result<Vec<N>> {
+    if let Some(x) = xs.iter().find(|x| x.cmp_real(x).is_none()) {
+        bail!(CaculatorError::NotReal(x.to_string()));
+    }
+    let mut xs = xs.to_vec();
+    xs.sort_by(|a, b| a.cmp_real(b).unwrap_or(Ordering::Equal));
+    Ok(xs)
+}
+
+/// The most frequent value, the smallest one on ties.
+fn mode<N: Number>(xs: &[N]) -> Result<N> {
+    let xs = sorted(xs)?;
+    let (mut best, mut best_run) = (0, 0);
+    let mut start = 0;
+    for i in 1..=xs.len() {
+        if i == xs.len() || xs[i] != xs[start] {
+            if i - start > best_run {
+                (best, best_run) = (start, i - start);
+            }
+            start = i;
+        }
+    }
+    Ok(xs[best].clone())
+}
+
+/// Interpolates linearly between the closest ranks, so the median of
+/// `1, 2, 3, 4` is `2.5`.
+fn percentile<N: Number>(xs: &[N], p: &N) -> Result<N> {
+    let xs = sorted(xs)?;
+    let (low, high) = (p.cmp_real(&N::parse("0")?), p.cmp_real(&N::parse("100")?));
+    if low.is_none_or(Ordering::is_lt) || high.is_none_or(Ordering::is_gt) {
+        bail!(CaculatorError::InvalidExpression(format!(
+            "percentile {}",
+            p
+        )));
+    }
+    let rank = p
+        .mul(&N::parse(&(xs.len() - 1).to_string())?)?
+        .div(&N::parse("100")?)?;
+    let k = rank.to_f64().unwrap_or(0.0).floor() as usize;
+    if k + 1 >= xs.len() {
+        return Ok(xs[xs.len() - 1].clone());
+    }
+    let frac = rank.sub(&N::parse(&k.to_string())?)?;
+    xs[k].add(&xs[k + 1].sub(&xs[k])?.mul(&frac)?)
+}
+
+/// Least-squares fit of `y = slope * x + intercept`, returned as `(slope, intercept)`.
+pub fn linreg<N: Number>(xs: &[N], ys: &[N]) -> Result<(N, N)> {
+    if xs.len() != ys.len() {
+        bail!(CaculatorError::ShapeMismatch(
+            format!("[{}]", xs.len()),
+            format!("[{}]", ys.len())
+        ));
+    }
+    if xs.len() < 2 {
+        bail!(CaculatorError::ArgumentCount("linreg".to_string(), 2));
+    }
+    let (mx, my) = (mean(xs)?, mean(ys)?);
+    let (mut sxy, mut sxx) = (N::parse("0")?, N::parse("0")?);
+    for (x, y) in xs.iter().zip(ys) {
+        let dx = x.sub(&mx)?;
+        sxy = sxy.add(&dx.mul(&y.sub(&my)?)?)?;
+        sxx = sxx.add(&dx.mul(&dx)?)?;
+    }
+    if sxx.is_zero() {
+        bail!(CaculatorError::DivideByZero);
+    }
+    let slope = sxy.div(&sxx)?;
+    let intercept = my.sub(&slope.mul(&mx)?)?;
+    Ok((slope, intercept))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::complex::Complex;
+    use crate::rational::Rational;
+
+    #[test]
+    fn test_aggregates() -> anyhow::Result<()> {
+        let xs = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let t_cases = vec![
+            ("count", 8.0),
+            ("sum", 40.0),
+            ("mean", 5.0),
+            ("median", 4.5),
+            ("mode", 4.0),
+            ("min", 2.0),
+            ("max", 9.0),
+            ("variance", 32.0 / 7.0),
+        ];
+        for tc in t_cases {
+            assert_eq!(aggregate(tc.0, &xs, None)?, tc.1, "{}", tc.0);
+        }
+        assert_eq!(aggregate("percentile", &xs, Some(&25.0))?, 4.0);
+        assert_eq!(aggregate("percentile", &xs, Some(&100.0))?, 9.0);
+        assert!(aggregate("percentile", &xs, Some(&101.0)).is_err());
+        assert!(aggregate("variance", &[1.0], None).is_err());
+        assert!(aggregate::<f64>("sum", &[], None).is_err());
+
+        let xs: Vec<Rational> = [1, 2, 4].map(Rational::from_int).to_vec();
+        assert_eq!(aggregate("mean", &xs, None)?.to_string(), "7/3");
+        // fractions that round to the same `f64` still sort exactly
+        let tiny = Rational::new(1, 10i128.pow(20))?;
+        let xs = [tiny.checked_mul(&Rational::from_int(2))?, tiny];
+        assert_eq!(aggregate("max", &xs, None)?, xs[0]);
+        let one = Rational::from_int(1);
+        let xs = [one.checked_add(&xs[0])?, one.checked_add(&tiny)?];
+        assert_eq!(aggregate("min", &xs, None)?, xs[1]);
+        assert!(aggregate::<Complex>("max", &[Complex::new(0.0, 1.0)], None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_linreg() -> anyhow::Result<()> {
+        let (slope, intercept) = linreg(&[1.0, 2.0, 3.0], &[3.0, 5.0, 7.0])?;
+        assert_eq!((slope, intercept), (2.0, 1.0));
+        assert!(linreg(&[1.0, 2.0], &[1.0]).is_err());
+        assert!(linreg(&[1.0, 1.0], &[1.0, 2.0]).is_err());
+        Ok(())
+    }
+}
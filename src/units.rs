@@ -0,0 +1,492 @@
+use anyhow::{bail, Result};
+use std::fmt;
+
+use crate::datetime;
+use crate::errors::CaculatorError;
+use crate::format::Format;
+use crate::number::Number;
+
+// money has no SI unit; its amounts are in the rate table's base currency
+const BASE_SYMBOLS: [&str; 8] = ["m", "kg", "s", "A", "K", "mol", "cd", "¤"];
+
+/// Exponents of the SI base units: length, mass, time, current,
+/// temperature, amount of substance and luminous intensity, plus money.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dimension(pub [i8; 8]);
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension([0; 8]);
+    pub const LENGTH: Dimension = Dimension([1, 0, 0, 0, 0, 0, 0, 0]);
+    pub const MASS: Dimension = Dimension([0, 1, 0, 0, 0, 0, 0, 0]);
+    pub const TIME: Dimension = Dimension([0, 0, 1, 0, 0, 0, 0, 0]);
+    pub const CURRENT: Dimension = Dimension([0, 0, 0, 1, 0, 0, 0, 0]);
+    pub const TEMPERATURE: Dimension = Dimension([0, 0, 0, 0, 1, 0, 0, 0]);
+    pub const AMOUNT: Dimension = Dimension([0, 0, 0, 0, 0, 1, 0, 0]);
+    pub const LUMINOSITY: Dimension = Dimension([0, 0, 0, 0, 0, 0, 1, 0]);
+    pub const MONEY: Dimension = Dimension([0, 0, 0, 0, 0, 0, 0, 1]);
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// Raises every exponent to `power`, failing if one would not stay whole.
+    pub fn scale(&self, power: f64) -> Option<Self> {
+        let mut out = [0; 8];
+        for (o, d) in out.iter_mut().zip(self.0) {
+            let v = d as f64 * power;
+            if v.fract() != 0.0 || v.abs() > i8::MAX as f64 {
+                return None;
+            }
+            *o = v as i8;
+        }
+        Some(Self(out))
+    }
+
+    // true if some base unit appears with opposite signs in `self` and `other`
+    fn cancels(&self, other: &Self) -> bool {
+        self.0
+            .iter()
+            .zip(other.0)
+            .any(|(a, b)| a.signum() * b.signum() < 0)
+    }
+
+    /// The dimension of a product, failing if an exponent leaves `i8`.
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self> {
+        let mut out = [0; 8];
+        for (o, (a, b)) in out.iter_mut().zip(self.0.iter().zip(rhs.0)) {
+            *o = checked(a.checked_add(b), || format!("{} * {}", self, rhs))?;
+        }
+        Ok(Self(out))
+    }
+
+    /// The dimension of a quotient.
+    pub fn checked_sub(&self, rhs: &Self) -> Result<Self> {
+        self.checked_add(&rhs.checked_neg()?)
+    }
+
+    /// The dimension of a reciprocal.
+    pub fn checked_neg(&self) -> Result<Self> {
+        let mut out = [0; 8];
+        for (o, d) in out.iter_mut().zip(self.0) {
+            *o = checked(d.checked_neg(), || format!("1/({})", self))?;
+        }
+        Ok(Self(out))
+    }
+}
+
+fn checked(exp: Option<i8>, op: impl Fn() -> String) -> Result<i8> {
+    match exp {
+        Some(exp) => Ok(exp),
+        None => bail!(CaculatorError::Overflow {
+            op: op(),
+            span: None
+        }),
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let part = |sym: &str, exp: i8| match exp.unsigned_abs() {
+            1 => sym.to_string(),
+            exp => format!("{}^{}", sym, exp),
+        };
+        let num: Vec<String> = BASE_SYMBOLS
+            .iter()
+            .zip(self.0)
+            .filter(|(_, e)| *e > 0)
+            .map(|(s, e)| part(s, e))
+            .collect();
+        let den: Vec<String> = BASE_SYMBOLS
+            .iter()
+            .zip(self.0)
+            .filter(|(_, e)| *e < 0)
+            .map(|(s, e)| part(s, e))
+            .collect();
+
+        match (num.is_empty(), den.is_empty()) {
+            (_, true) => write!(f, "{}", num.join("*")),
+            (true, false) => write!(f, "1/{}", den.join("/")),
+            (false, false) => write!(f, "{}/{}", num.join("*"), den.join("/")),
+        }
+    }
+}
+
+// the factors of a display unit like `kg*m/s^2` with their exponents
+fn factors(name: &str) -> Option<Vec<(&str, i32)>> {
+    let mut out = Vec::new();
+    for (i, part) in name.split('/').enumerate() {
+        let sign = if i == 0 { 1 } else { -1 };
+        for factor in part.split('*').filter(|f| !f.is_empty() && *f != "1") {
+            let (base, exp) = match factor.split_once('^') {
+                Some((base, exp)) => (base, exp.parse::<i32>().ok()?),
+                None => (factor, 1),
+            };
+            out.push((base, sign * exp));
+        }
+    }
+    Some(out)
+}
+
+// the display unit of `a*b`, or of `a/b` for a `sign` of -1, with equal
+// units gathered into powers: `m` times `m` is `m^2`
+fn combine(a: &str, b: &str, sign: i32) -> Option<String> {
+    let mut all: Vec<(&str, i32)> = Vec::new();
+    let rhs = factors(b)?
+        .into_iter()
+        .map(|(base, exp)| (base, sign * exp));
+    for (base, exp) in factors(a)?.into_iter().chain(rhs) {
+        match all.iter_mut().find(|(b, _)| *b == base) {
+            Some((_, e)) => *e += exp,
+            None => all.push((base, exp)),
+        }
+    }
+    let part = |base: &str, exp: i32| match exp.abs() {
+        1 => base.to_string(),
+        exp => format!("{}^{}", base, exp),
+    };
+    let num: Vec<String> = all
+        .iter()
+        .filter(|f| f.1 > 0)
+        .map(|f| part(f.0, f.1))
+        .collect();
+    let den: Vec<String> = all
+        .iter()
+        .filter(|f| f.1 < 0)
+        .map(|f| part(f.0, f.1))
+        .collect();
+    match (num.is_empty(), den.is_empty()) {
+        (true, true) => None,
+        (_, true) => Some(num.join("*")),
+        (true, false) => Some(format!("1/{}", den.join("/"))),
+        (false, false) => Some(format!("{}/{}", num.join("*"), den.join("/"))),
+    }
+}
+
+/// A value with a physical dimension, stored in SI base units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity<N> {
+    pub value: N,
+    pub dim: Dimension,
+    // unit to display the value in, with the SI value of one such unit
+    pub unit: Option<(String, N)>,
+}
+
+impl<N: Number> Quantity<N> {
+    // sums are shown in the unit of the left operand, or in SI (human units
+    // for durations) if the left operand has none
+    pub fn add(&self, rhs: &Self) -> Result<Self> {
+        self.check_same(rhs)?;
+        Ok(Self {
+            value: self.value.add(&rhs.value)?,
+            dim: self.dim,
+            unit: self.unit.clone(),
+        })
+    }
+
+    pub fn sub(&self, rhs: &Self) -> Result<Self> {
+        self.check_same(rhs)?;
+        Ok(Self {
+            value: self.value.sub(&rhs.value)?,
+            dim: self.dim,
+            unit: self.unit.clone(),
+        })
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Result<Self> {
+        let unit = match (&self.unit, &rhs.unit) {
+            (Some((a, fa)), Some((b, fb))) if !self.dim.cancels(&rhs.dim) => combine(a, b, 1)
+                .map(|name| fa.mul(fb).map(|f| (name, f)))
+                .transpose()?,
+            _ => None,
+        };
+        Ok(Self {
+            value: self.value.mul(&rhs.value)?,
+            dim: self.dim.checked_add(&rhs.dim)?,
+            unit,
+        })
+    }
+
+    pub fn div(&self, rhs: &Self) -> Result<Self> {
+        if rhs.value.is_zero() {
+            bail!(CaculatorError::DivideByZero);
+        }
+        let unit = match (&self.unit, &rhs.unit) {
+            (Some((a, fa)), Some((b, fb))) if !self.dim.cancels(&rhs.dim.checked_neg()?) => {
+                combine(a, b, -1)
+                    .map(|name| fa.div(fb).map(|f| (name, f)))
+                    .transpose()?
+            }
+            _ => None,
+        };
+        Ok(Self {
+            value: self.value.div(&rhs.value)?,
+            dim: self.dim.checked_sub(&rhs.dim)?,
+            unit,
+        })
+    }
+
+    pub fn pow(&self, exp: &N) -> Result<Self> {
+        let power = exp.to_f64();
+        let Some(dim) = power.and_then(|p| self.dim.scale(p)) else {
+            bail!(CaculatorError::DimensionMismatch(
+                self.dim.to_string(),
+                format!("^{}", exp)
+            ));
+        };
+        let unit = match (&self.unit, power.and_then(|p| self.unit_power(p))) {
+            (Some((_, factor)), Some(name)) => Some((name, factor.pow(exp)?)),
+            _ => None,
+        };
+        Ok(Self {
+            value: self.value.pow(exp)?,
+            dim,
+            unit,
+        })
+    }
+
+    /// Scales the quantity by a plain number, keeping its display unit.
+    pub fn scale(&self, k: &N) -> Result<Self> {
+        Ok(Self {
+            value: self.value.mul(k)?,
+            dim: self.dim,
+            unit: self.unit.clone(),
+        })
+    }
+
+    /// Expresses the quantity in the display unit of `target`.
+    pub fn convert(&self, target: &Self) -> Result<Self> {
+        self.check_same(target)?;
+        Ok(Self {
+            value: self.value.clone(),
+            dim: self.dim,
+            unit: target.unit.clone(),
+        })
+    }
+
+    // the display unit raised to `power`: `m^2` squared is `m^4`, its square root `m`
+    fn unit_power(&self, power: f64) -> Option<String> {
+        let (name, _) = self.unit.as_ref()?;
+        let (base, exp) = match name.split_once('^') {
+            Some((base, exp)) => (base, exp.parse::<f64>().ok()?),
+            None => (name.as_str(), 1.0),
+        };
+        if !base.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+
+        let exp = exp * power;
+        if exp.fract() != 0.0 {
+            return None;
+        }
+        if exp == 1.0 {
+            return Some(base.to_string());
+        }
+        Some(format!("{}^{}", base, exp))
+    }
+
+    fn check_same(&self, rhs: &Self) -> Result<()> {
+        if self.dim != rhs.dim {
+            bail!(CaculatorError::DimensionMismatch(
+                self.dim.to_string(),
+                rhs.dim.to_string()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<N: Number> Quantity<N> {
+    /// Writes the value in its display unit with the given notation.
+    pub fn format(&self, format: &Format) -> String {
+        if let Some((name, factor)) = &self.unit {
+            if let Ok(v) = self.value.div(factor) {
+                return format!("{} {}", v.format(format), name);
+            }
+        }
+        if self.dim.is_none() {
+            return self.value.format(format);
+        }
+        if let (Dimension::TIME, Some(secs)) = (self.dim, self.value.to_f64()) {
+            return datetime::format_duration(secs);
+        }
+        format!("{} {}", self.value.format(format), self.dim)
+    }
+}
+
+impl<N: Number> fmt::Display for Quantity<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(&Format::default()))
+    }
+}
+
+// name, dimension, SI value of one unit, whether SI prefixes apply
+type UnitDef = (&'static str, Dimension, &'static str, bool);
+
+const UNITS: &[UnitDef] = &[
+    // SI base units; the kilogram is prefixed through the gram
+    ("m", Dimension::LENGTH, "1", true),
+    ("g", Dimension::MASS, "0.001", true),
+    ("s", Dimension::TIME, "1", true),
+    ("A", Dimension::CURRENT, "1", true),
+    ("K", Dimension::TEMPERATURE, "1", true),
+    ("mol", Dimension::AMOUNT, "1", true),
+    ("cd", Dimension::LUMINOSITY, "1", true),
+    // derived SI units
+    ("Hz", Dimension([0, 0, -1, 0, 0, 0, 0, 0]), "1", true),
+    ("N", Dimension([1, 1, -2, 0, 0, 0, 0, 0]), "1", true),
+    ("Pa", Dimension([-1, 1, -2, 0, 0, 0, 0, 0]), "1", true),
+    ("J", Dimension([2, 1, -2, 0, 0, 0, 0, 0]), "1", true),
+    ("W", Dimension([2, 1, -3, 0, 0, 0, 0, 0]), "1", true),
+    ("C", Dimension([0, 0, 1, 1, 0, 0, 0, 0]), "1", true),
+    ("V", Dimension([2, 1, -3, -1, 0, 0, 0, 0]), "1", true),
+    ("ohm", Dimension([2, 1, -3, -2, 0, 0, 0, 0]), "1", true),
+    ("F", Dimension([-2, -1, 4, 2, 0, 0, 0, 0]), "1", true),
+    ("L", Dimension([3, 0, 0, 0, 0, 0, 0, 0]), "0.001", true),
+    ("t", Dimension::MASS, "1000", false),
+    ("min", Dimension::TIME, "60", false),
+    ("h", Dimension::TIME, "3600", false),
+    ("day", Dimension::TIME, "86400", false),
+    ("days", Dimension::TIME, "86400", false),
+    ("week", Dimension::TIME, "604800", false),
+    ("weeks", Dimension::TIME, "604800", false),
+    // imperial and US customary units
+    ("in", Dimension::LENGTH, "0.0254", false),
+    ("ft", Dimension::LENGTH, "0.3048", false),
+    ("yd", Dimension::LENGTH, "0.9144", false),
+    ("mi", Dimension::LENGTH, "1609.344", false),
+    ("oz", Dimension::MASS, "0.028349523125", false),
+    ("lb", Dimension::MASS, "0.45359237", false),
+    (
+        "mph",
+        Dimension([1, 0, -1, 0, 0, 0, 0, 0]),
+        "0.44704",
+        false,
+    ),
+    (
+        "gal",
+        Dimension([3, 0, 0, 0, 0, 0, 0, 0]),
+        "0.003785411784",
+        false,
+    ),
+    (
+        "psi",
+        Dimension([-1, 1, -2, 0, 0, 0, 0, 0]),
+        "6894.757293168",
+        false,
+    ),
+];
+
+const PREFIXES: &[(&str, i32)] = &[
+    ("Y", 24),
+    ("Z", 21),
+    ("E", 18),
+    ("P", 15),
+    ("T", 12),
+    ("G", 9),
+    ("M", 6),
+    ("k", 3),
+    ("h", 2),
+    ("da", 1),
+    ("d", -1),
+    ("c", -2),
+    ("m", -3),
+    ("u", -6),
+    ("n", -9),
+    ("p", -12),
+    ("f", -15),
+    ("a", -18),
+    ("z", -21),
+    ("y", -24),
+];
+
+/// Whether `name` is a known unit, with or without an SI prefix.
+pub fn is_unit(name: &str) -> bool {
+    matches!(unit::<f64>(name), Ok(Some(_)))
+}
+
+/// Looks up a unit such as `km` or `ft` as a quantity of one such unit.
+pub fn unit<N: Number>(name: &str) -> Result<Option<Quantity<N>>> {
+    // an exact name wins over a prefixed one, so `min` is minutes, not milli-inches
+    if let Some((_, dim, factor, _)) = UNITS.iter().find(|u| u.0 == name) {
+        return Ok(Some(quantity(name, *dim, N::parse(factor)?)));
+    }
+
+    for (prefix, exp) in PREFIXES {
+        let Some(rest) = name.strip_prefix(prefix) else {
+            continue;
+        };
+        if let Some((_, dim, factor, true)) = UNITS.iter().find(|u| u.0 == rest) {
+            let scale = N::parse("10")?.pow(&N::parse(&exp.abs().to_string())?)?;
+            let factor = N::parse(factor)?;
+            let factor = if *exp < 0 {
+                factor.div(&scale)?
+            } else {
+                factor.mul(&scale)?
+            };
+            return Ok(Some(quantity(name, *dim, factor)));
+        }
+    }
+
+    Ok(None)
+}
+
+fn quantity<N: Number>(name: &str, dim: Dimension, factor: N) -> Quantity<N> {
+    Quantity {
+        value: factor.clone(),
+        dim,
+        unit: Some((name.to_string(), factor)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_names() {
+        assert_eq!(combine("m", "m", 1).as_deref(), Some("m^2"));
+        assert_eq!(combine("m^2", "m", 1).as_deref(), Some("m^3"));
+        assert_eq!(combine("kg*m", "s^2", -1).as_deref(), Some("kg*m/s^2"));
+        assert_eq!(combine("m/s", "s", -1).as_deref(), Some("m/s^2"));
+        assert_eq!(combine("km/h", "h", 1).as_deref(), Some("km"));
+        assert_eq!(combine("1/s", "s", -1).as_deref(), Some("1/s^2"));
+        assert_eq!(combine("m", "m", -1), None);
+    }
+
+    #[test]
+    fn test_unit_lookup() -> anyhow::Result<()> {
+        let km = unit::<f64>("km")?.unwrap();
+        assert_eq!(km.value, 1000.0);
+        assert_eq!(km.dim, Dimension::LENGTH);
+
+        let mg = unit::<f64>("mg")?.unwrap();
+        assert_eq!(mg.value, 1e-6);
+
+        assert_eq!(unit::<f64>("min")?.unwrap().value, 60.0);
+        assert!(unit::<f64>("kft")?.is_none());
+        assert!(unit::<f64>("x")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dimension_display() -> anyhow::Result<()> {
+        let speed = Dimension::LENGTH.checked_sub(&Dimension::TIME)?;
+        assert_eq!(speed.to_string(), "m/s");
+        let energy = Dimension([2, 1, -2, 0, 0, 0, 0, 0]);
+        assert_eq!(energy.to_string(), "m^2*kg/s^2");
+        assert_eq!(Dimension::TIME.checked_neg()?.to_string(), "1/s");
+        Ok(())
+    }
+
+    #[test]
+    fn test_dimension_overflow() {
+        let big = Dimension([127, 0, 0, 0, 0, 0, 0, -128]);
+        let err = big.checked_add(&Dimension::LENGTH).unwrap_err();
+        assert_eq!(err.to_string(), "number overflow in: m^127/¤^128 * m");
+        assert!(big.checked_neg().is_err());
+        assert!(big.checked_sub(&Dimension::MONEY).is_err());
+        // exponents of opposite sign whose product leaves `i8`
+        let a = Dimension([100, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(a.cancels(&Dimension([-100, 0, 0, 0, 0, 0, 0, 0])));
+    }
+}
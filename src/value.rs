@@ -0,0 +1,847 @@
+use anyhow::{bail, Result};
+use std::fmt;
+
+use crate::combinatorics;
+use crate::datetime::Date;
+use crate::errors::{CaculatorError, Domain};
+use crate::expr::Expr;
+use crate::finance;
+use crate::format::Format;
+use crate::interval::Interval;
+use crate::matrix::Matrix;
+use crate::number::Number;
+use crate::numtheory;
+use crate::poly;
+use crate::stats;
+use crate::units::{Dimension, Quantity};
+use crate::word;
+
+/// A value on the evaluator stack.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<N> {
+    Num(N),
+    Quantity(Quantity<N>),
+    Date(Date),
+    Interval(Interval),
+    Matrix(Matrix<N>),
+    Expr(Expr<N>),
+}
+
+/// The function a `[a, b, ...]` literal is read as.
+pub const LIST: &str = "[";
+
+// the longest amortization table, a century of monthly payments
+const MAX_PERIODS: f64 = 1200.0;
+
+impl<N: Number> Value<N> {
+    /// Returns the plain number, failing for values with a unit.
+    pub fn to_number(&self) -> Result<&N> {
+        match self {
+            Value::Num(n) => Ok(n),
+            _ => bail!(CaculatorError::DimensionMismatch(
+                self.dimension(),
+                "1".to_string()
+            )),
+        }
+    }
+
+    pub fn add(&self, rhs: &Self) -> Result<Self> {
+        if let Some(e) = self.symbolic(rhs, '+')? {
+            return Ok(e);
+        }
+        match (self, rhs) {
+            (Value::Num(a), Value::Num(b)) => Ok(Value::Num(a.add(b)?)),
+            (Value::Quantity(a), Value::Quantity(b)) => Ok(a.add(b)?.into()),
+            (Value::Date(d), Value::Quantity(q)) | (Value::Quantity(q), Value::Date(d)) => {
+                Ok(Value::Date(d.add_secs(duration_secs(q)?)?))
+            }
+            (Value::Matrix(a), Value::Matrix(b)) => Ok(Value::Matrix(a.zip(b, N::add)?)),
+            _ => self.intervals(rhs, Interval::add),
+        }
+    }
+
+    pub fn sub(&self, rhs: &Self) -> Result<Self> {
+        if let Some(e) = self.symbolic(rhs, '-')? {
+            return Ok(e);
+        }
+        match (self, rhs) {
+            (Value::Num(a), Value::Num(b)) => Ok(Value::Num(a.sub(b)?)),
+            (Value::Quantity(a), Value::Quantity(b)) => Ok(a.sub(b)?.into()),
+            (Value::Date(d), Value::Quantity(q)) => {
+                Ok(Value::Date(d.add_secs(-duration_secs(q)?)?))
+            }
+            (Value::Date(a), Value::Date(b)) => Ok(Value::Quantity(Quantity {
+                value: N::parse(&a.secs_since(b).unsigned_abs().to_string()).and_then(|v| {
+                    if a < b {
+                        v.neg()
+                    } else {
+                        Ok(v)
+                    }
+                })?,
+                dim: Dimension::TIME,
+                unit: None,
+            })),
+            (Value::Matrix(a), Value::Matrix(b)) => Ok(Value::Matrix(a.zip(b, N::sub)?)),
+            _ => self.intervals(rhs, Interval::sub),
+        }
+    }
+
+    /// A number or expression as an expression, failing for other values.
+    pub fn to_expr(&self) -> Result<Expr<N>> {
+        match self {
+            Value::Expr(e) => Ok(e.clone()),
+            _ => Ok(Expr::Num(self.to_number()?.clone())),
+        }
+    }
+
+    /// An expression that folded down to a number becomes that number.
+    pub fn from_expr(e: Expr<N>) -> Self {
+        match e {
+            Expr::Num(n) => Value::Num(n),
+            e => Value::Expr(e),
+        }
+    }
+
+    // arithmetic on an expression builds a bigger expression
+    fn symbolic(&self, rhs: &Self, op: char) -> Result<Option<Self>> {
+        match (self, rhs) {
+            (Value::Expr(_), _) | (_, Value::Expr(_)) => Ok(Some(Self::from_expr(Expr::op(
+                op,
+                self.to_expr()?,
+                rhs.to_expr()?,
+            )?))),
+            _ => Ok(None),
+        }
+    }
+
+    /// The derivative with respect to `var`; plain numbers are constants.
+    pub fn diff(&self, var: &str) -> Result<Self> {
+        Ok(Self::from_expr(self.to_expr()?.diff(var)?))
+    }
+
+    /// Rewrites an expression into a simpler equivalent one.
+    pub fn simplify(&self) -> Result<Self> {
+        Ok(Self::from_expr(self.to_expr()?.simplify()?))
+    }
+
+    /// Drops the display unit, so durations such as `1h 30min` print in human units.
+    pub fn without_unit(self) -> Self {
+        match self {
+            Value::Quantity(q) => Value::Quantity(Quantity { unit: None, ..q }),
+            v => v,
+        }
+    }
+
+    pub fn mul(&self, rhs: &Self) -> Result<Self> {
+        if let Some(e) = self.symbolic(rhs, '*')? {
+            return Ok(e);
+        }
+        match (self, rhs) {
+            (Value::Num(a), Value::Num(b)) => Ok(Value::Num(a.mul(b)?)),
+            (Value::Quantity(a), Value::Quantity(b)) => Ok(a.mul(b)?.into()),
+            (Value::Quantity(q), Value::Num(k)) | (Value::Num(k), Value::Quantity(q)) => {
+                Ok(q.scale(k)?.into())
+            }
+            (Value::Matrix(a), Value::Matrix(b)) => Ok(Value::Matrix(a.mul(b)?)),
+            (Value::Matrix(m), Value::Num(k)) | (Value::Num(k), Value::Matrix(m)) => {
+                Ok(Value::Matrix(m.map(|x| x.mul(k))?))
+            }
+            _ => self.intervals(rhs, Interval::mul),
+        }
+    }
+
+    pub fn div(&self, rhs: &Self) -> Result<Self> {
+        if let Some(e) = self.symbolic(rhs, '/')? {
+            return Ok(e);
+        }
+        match (self, rhs) {
+            (Value::Num(a), Value::Num(b)) => {
+                if b.is_zero() {
+                    bail!(CaculatorError::DivideByZero);
+                }
+                Ok(Value::Num(a.div(b)?))
+            }
+            (Value::Quantity(a), Value::Quantity(b)) => Ok(a.div(b)?.into()),
+            (Value::Quantity(q), Value::Num(k)) => {
+                if k.is_zero() {
+                    bail!(CaculatorError::DivideByZero);
+                }
+                Ok(Value::Quantity(Quantity {
+                    value: q.value.div(k)?,
+                    dim: q.dim,
+                    unit: q.unit.clone(),
+                }))
+            }
+            (Value::Num(k), Value::Quantity(q)) => {
+                if q.value.is_zero() {
+                    bail!(CaculatorError::DivideByZero);
+                }
+                Ok(Quantity {
+                    value: k.div(&q.value)?,
+                    dim: q.dim.checked_neg()?,
+                    unit: None,
+                }
+                .into())
+            }
+            (Value::Matrix(m), Value::Num(k)) => {
+                if k.is_zero() {
+                    bail!(CaculatorError::DivideByZero);
+                }
+                Ok(Value::Matrix(m.map(|x| x.div(k))?))
+            }
+            _ => self.intervals(rhs, Interval::div),
+        }
+    }
+
+    /// The `.*` operator: multiplies vectors and matrices entry by entry.
+    pub fn mul_elements(&self, rhs: &Self) -> Result<Self> {
+        match (self, rhs) {
+            (Value::Matrix(a), Value::Matrix(b)) => Ok(Value::Matrix(a.zip(b, N::mul)?)),
+            _ => self.mul(rhs),
+        }
+    }
+
+    /// The `./` operator: divides vectors and matrices entry by entry.
+    pub fn div_elements(&self, rhs: &Self) -> Result<Self> {
+        match (self, rhs) {
+            (Value::Matrix(a), Value::Matrix(b)) => Ok(Value::Matrix(a.zip(b, |x, y| {
+                if y.is_zero() {
+                    bail!(CaculatorError::DivideByZero);
+                }
+                x.div(y)
+            })?)),
+            _ => self.div(rhs),
+        }
+    }
+
+    /// The `\` operator: `A \ b` solves `A x = b`.
+    pub fn left_div(&self, rhs: &Self) -> Result<Self> {
+        match (self, rhs) {
+            (Value::Matrix(a), Value::Matrix(b)) => Ok(Value::Matrix(a.solve(b)?)),
+            (Value::Matrix(_), _) | (_, Value::Matrix(_)) => bail!(self.mismatch(rhs)),
+            _ => rhs.div(self),
+        }
+    }
+
+    /// The `%` operator: the integer remainder with the sign of `rhs`, so
+    /// `-7 % 3` is `2`.
+    pub fn rem(&self, rhs: &Self) -> Result<Self> {
+        let (Value::Num(a), Value::Num(b)) = (self, rhs) else {
+            bail!(self.mismatch(rhs));
+        };
+        let (Ok(x), Ok(m)) = (word::to_int(a), word::to_int(b)) else {
+            bail!(CaculatorError::DomainError {
+                kind: Domain::NotInteger,
+                op: format!("{} % {}", a, b),
+                span: None,
+            });
+        };
+        if m == 0 {
+            bail!(CaculatorError::DivideByZero);
+        }
+        Ok(Value::Num(word::from_int(numtheory::modulo(x, m))?))
+    }
+
+    pub fn pow(&self, rhs: &Self) -> Result<Self> {
+        if let Some(e) = self.symbolic(rhs, '^')? {
+            return Ok(e);
+        }
+        match (self, rhs) {
+            (Value::Interval(_), _) | (_, Value::Interval(_)) => self.intervals(rhs, Interval::pow),
+            (Value::Num(a), exp) => Ok(Value::Num(a.pow(exp.to_number()?)?)),
+            (Value::Quantity(q), exp) => Ok(q.pow(exp.to_number()?)?.into()),
+            (Value::Matrix(m), exp) => Ok(Value::Matrix(m.pow(exp.to_number()?)?)),
+            (Value::Date(_) | Value::Expr(_), _) => bail!(self.mismatch(rhs)),
+        }
+    }
+
+    /// `mid ± radius`
+    pub fn plus_minus(&self, rhs: &Self) -> Result<Self> {
+        let (mid, radius) = (self.to_interval()?, rhs.to_interval()?);
+        Ok(Value::Interval(Interval::uncertain(
+            mid.mid(),
+            radius.hi.abs().max(radius.lo.abs()),
+        )?))
+    }
+
+    // applies `op` on intervals, widening plain real numbers to points
+    fn intervals(
+        &self,
+        rhs: &Self,
+        op: fn(&Interval, &Interval) -> Result<Interval>,
+    ) -> Result<Self> {
+        match (self, rhs) {
+            (Value::Interval(_), Value::Interval(_) | Value::Num(_))
+            | (Value::Num(_), Value::Interval(_)) => Ok(Value::Interval(op(
+                &self.to_interval()?,
+                &rhs.to_interval()?,
+            )?)),
+            (Value::Interval(_), Value::Quantity(q)) | (Value::Quantity(q), Value::Interval(_)) => {
+                bail!(CaculatorError::IntervalUnit(q.dim.to_string()))
+            }
+            _ => bail!(self.mismatch(rhs)),
+        }
+    }
+
+    fn to_interval(&self) -> Result<Interval> {
+        match self {
+            Value::Interval(x) => Ok(*x),
+            Value::Quantity(q) => bail!(CaculatorError::IntervalUnit(q.dim.to_string())),
+            _ => match self.to_number()?.to_f64() {
+                Some(x) => Ok(Interval::point(x)),
+                None => bail!(CaculatorError::NotReal(self.to_string())),
+            },
+        }
+    }
+
+    pub fn neg(&self) -> Result<Self> {
+        match self {
+            Value::Num(a) => Ok(Value::Num(a.neg()?)),
+            Value::Quantity(q) => Ok(Value::Quantity(Quantity {
+                value: q.value.neg()?,
+                dim: q.dim,
+                unit: q.unit.clone(),
+            })),
+            Value::Date(_) => bail!(CaculatorError::UnsupportedOperator('-')),
+            Value::Interval(x) => Ok(Value::Interval(x.neg()?)),
+            Value::Matrix(m) => Ok(Value::Matrix(m.map(N::neg)?)),
+            Value::Expr(e) => Ok(Self::from_expr(Expr::negate(e.clone())?)),
+        }
+    }
+
+    /// The `to` operator: expresses `self` in the unit of `rhs`.
+    pub fn convert(&self, rhs: &Self) -> Result<Self> {
+        match (self, rhs) {
+            (Value::Quantity(a), Value::Quantity(b)) => Ok(Value::Quantity(a.convert(b)?)),
+            _ => bail!(self.mismatch(rhs)),
+        }
+    }
+
+    /// Calls a named function, passing plain numbers through to the backend.
+    pub fn call(name: &str, args: &[Self]) -> Result<Self> {
+        if args.iter().any(|a| matches!(a, Value::Expr(_))) {
+            let args = args.iter().map(Self::to_expr).collect::<Result<_>>()?;
+            return Ok(Value::Expr(Expr::call(name, args)));
+        }
+        if name == LIST {
+            return Self::list(args);
+        }
+        if stats::is_aggregate(name) || name == "linreg" {
+            return Self::aggregate(name, args);
+        }
+        if poly::arity(name).is_some() {
+            return Self::poly(name, args);
+        }
+        if finance::arity(name).is_some() {
+            return Self::finance(name, args);
+        }
+        if numtheory::is_number_theory(name) {
+            return Self::number_theory(name, args);
+        }
+        if combinatorics::is_combinatorial(name) {
+            return Self::combinatorics(name, args);
+        }
+        match (name, args) {
+            ("dot", [Value::Matrix(a), Value::Matrix(b)]) => return Ok(Value::Num(a.dot(b)?)),
+            ("cross", [Value::Matrix(a), Value::Matrix(b)]) => {
+                return Ok(Value::Matrix(a.cross(b)?))
+            }
+            ("det", [Value::Matrix(m)]) => return Ok(Value::Num(m.det()?)),
+            ("inv", [Value::Matrix(m)]) => return Ok(Value::Matrix(m.inv()?)),
+            ("transpose", [Value::Matrix(m)]) => return Ok(Value::Matrix(m.transpose()?)),
+            ("norm", [Value::Matrix(m)]) => return Ok(Value::Num(m.norm()?)),
+            // other functions apply to every entry
+            (_, [Value::Matrix(m)]) => {
+                return Ok(Value::Matrix(
+                    m.map(|x| N::call(name, std::slice::from_ref(x)))?,
+                ))
+            }
+            _ => {}
+        }
+        if name == "interval" {
+            let [lo, hi] = args else {
+                bail!(CaculatorError::ArgumentCount(name.to_string(), 2));
+            };
+            let (lo, hi) = (lo.to_interval()?, hi.to_interval()?);
+            return Ok(Value::Interval(Interval::new(lo.lo, hi.hi)?));
+        }
+        if let [Value::Interval(x)] = args {
+            let bound = match name {
+                "lo" => x.lo,
+                "hi" => x.hi,
+                "mid" => x.mid(),
+                "width" => x.width(),
+                _ => return Ok(Value::Interval(x.call(name)?)),
+            };
+            return Ok(Value::Num(from_f64(bound)?));
+        }
+        if let [Value::Quantity(q)] = args {
+            match name {
+                "sqrt" => return Ok(q.pow(&N::parse("0.5")?)?.into()),
+                "abs" => {
+                    return Ok(Value::Quantity(Quantity {
+                        value: N::call("abs", std::slice::from_ref(&q.value))?,
+                        dim: q.dim,
+                        unit: q.unit.clone(),
+                    }))
+                }
+                _ => {}
+            }
+        }
+
+        let nums = args
+            .iter()
+            .map(|v| v.to_number().cloned())
+            .collect::<Result<Vec<N>>>()?;
+        Ok(Value::Num(N::call(name, &nums)?))
+    }
+
+    // `[1, 2, 3]` is a vector, `[[1, 2], [3, 4]]` a matrix with those rows
+    fn list(args: &[Self]) -> Result<Self> {
+        if let Some(Value::Matrix(_)) = args.first() {
+            let rows = args
+                .iter()
+                .map(|row| match row {
+                    Value::Matrix(m) => Ok(m.clone()),
+                    _ => bail!(CaculatorError::ShapeMismatch(
+                        args[0].dimension(),
+                        row.dimension()
+                    )),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Value::Matrix(Matrix::from_rows(&rows)?));
+        }
+        let entries = args
+            .iter()
+            .map(|v| v.to_number().cloned())
+            .collect::<Result<Vec<N>>>()?;
+        Ok(Value::Matrix(Matrix::vector(entries)?))
+    }
+
+    // statistics of `f([1, 2, 3])` or `f(1, 2, 3)`, with the percentage of
+    // `percentile` last; `linreg(xs, ys)` gives `[slope, intercept]`
+    fn aggregate(name: &str, args: &[Self]) -> Result<Self> {
+        if name == "linreg" {
+            let [Value::Matrix(xs), Value::Matrix(ys)] = args else {
+                bail!(CaculatorError::ArgumentCount(name.to_string(), 2));
+            };
+            let (slope, intercept) = stats::linreg(&xs.data, &ys.data)?;
+            return Ok(Value::Matrix(Matrix::vector(vec![slope, intercept])?));
+        }
+        let (args, p) = match (name, args) {
+            ("percentile", [xs @ .., p]) => (xs, Some(p.to_number()?)),
+            _ => (args, None),
+        };
+        let xs = match args {
+            [Value::Matrix(m)] => m.data.clone(),
+            _ => args
+                .iter()
+                .map(|v| v.to_number().cloned())
+                .collect::<Result<_>>()?,
+        };
+        Ok(Value::Num(stats::aggregate(name, &xs, p)?))
+    }
+
+    // polynomials are vectors of coefficients, the highest power first
+    fn poly(name: &str, args: &[Self]) -> Result<Self> {
+        let coeffs = |v: &Self| match v {
+            Value::Matrix(m) if m.vector => Ok(m.data.clone()),
+            Value::Num(n) => Ok(vec![n.clone()]),
+            _ => bail!(CaculatorError::ShapeMismatch(
+                v.dimension(),
+                "[n]".to_string()
+            )),
+        };
+        let vector = |xs: Vec<N>| Ok(Value::Matrix(Matrix::vector(xs)?));
+        match (name, args) {
+            ("polyval", [p, Value::Matrix(xs)]) => {
+                let p = coeffs(p)?;
+                Ok(Value::Matrix(xs.map(|x| poly::eval(&p, x))?))
+            }
+            ("polyval", [p, x]) => Ok(Value::Num(poly::eval(&coeffs(p)?, x.to_number()?)?)),
+            ("polymul", [p, q]) => vector(poly::mul(&coeffs(p)?, &coeffs(q)?)?),
+            ("polydiv", [p, q]) => vector(poly::div(&coeffs(p)?, &coeffs(q)?)?.0),
+            ("polyrem", [p, q]) => vector(poly::div(&coeffs(p)?, &coeffs(q)?)?.1),
+            ("polyfit", [xs, ys, degree]) => {
+                let degree = degree.to_number()?;
+                let d = degree
+                    .to_f64()
+                    .filter(|d| d.fract() == 0.0 && (0.0..64.0).contains(d));
+                let Some(d) = d else {
+                    bail!(CaculatorError::InvalidExpression(degree.to_string()));
+                };
+                vector(poly::fit(&coeffs(xs)?, &coeffs(ys)?, d as usize)?)
+            }
+            ("polyroots", [p]) => {
+                let p = coeffs(p)?
+                    .iter()
+                    .map(|c| c.to_f64().ok_or(CaculatorError::NotReal(c.to_string())))
+                    .collect::<Result<Vec<f64>, _>>()?;
+                let roots = poly::roots(&p)?
+                    .into_iter()
+                    .map(|z| {
+                        let re = from_f64::<N>(z.re)?;
+                        if z.im == 0.0 {
+                            return Ok(re);
+                        }
+                        // only backends with an `i` have the complex roots
+                        let i =
+                            N::constant("i").map_err(|_| CaculatorError::NotReal(z.to_string()))?;
+                        re.add(&from_f64::<N>(z.im)?.mul(&i)?)
+                    })
+                    .collect::<Result<_>>()?;
+                vector(roots)
+            }
+            _ => bail!(CaculatorError::ArgumentCount(
+                name.to_string(),
+                poly::arity(name).unwrap_or_default()
+            )),
+        }
+    }
+
+    // money over real numbers; amounts come as a list or one by one, and the
+    // optional arguments default like in spreadsheets
+    fn finance(name: &str, args: &[Self]) -> Result<Self> {
+        let count = finance::arity(name).unwrap_or(0..=0);
+        if !count.contains(&args.len()) {
+            let count = if args.len() < *count.start() {
+                count.start()
+            } else {
+                count.end()
+            };
+            bail!(CaculatorError::ArgumentCount(name.to_string(), *count));
+        }
+        let real = |n: &N| match n.to_f64() {
+            Some(x) => Ok(x),
+            None => bail!(CaculatorError::NotReal(n.to_string())),
+        };
+        let reals = |vs: &[Self]| match vs {
+            [Value::Matrix(m)] => m.data.iter().map(real).collect::<Result<Vec<f64>>>(),
+            _ => vs.iter().map(|v| real(v.to_number()?)).collect(),
+        };
+        let arg = |i: usize, default: f64| match args.get(i) {
+            Some(v) => real(v.to_number()?),
+            None => Ok(default),
+        };
+        let num = |x: f64| Ok(Value::Num(from_f64(x)?));
+        let no_root = || CaculatorError::NoRoot(name.to_string());
+
+        match name {
+            // the annuity functions take numbers only
+            "pmt" | "pv" | "fv" | "nper" | "rate" | "amort" => {
+                let (a, b, c, d) = (arg(0, 0.0)?, arg(1, 0.0)?, arg(2, 0.0)?, arg(3, 0.0)?);
+                let due = arg(4, 0.0)? != 0.0;
+                match name {
+                    "pmt" => num(finance::pmt(a, b, c, d, due)),
+                    "pv" => num(finance::pv(a, b, c, d, due)),
+                    "fv" => num(finance::fv(a, b, c, d, due)),
+                    "nper" => num(finance::nper(a, b, c, d, due).ok_or_else(no_root)?),
+                    "rate" => {
+                        let r = finance::rate(a, b, c, d, due, arg(5, 0.1)?);
+                        num(r.ok_or_else(no_root)?)
+                    }
+                    // one row per period: period, payment, interest, principal, balance
+                    _ => {
+                        if b.fract() != 0.0 || !(1.0..=MAX_PERIODS).contains(&b) {
+                            bail!(CaculatorError::InvalidExpression(args[1].to_string()));
+                        }
+                        let rows = finance::amortization(a, b as usize, c);
+                        let data = rows
+                            .iter()
+                            .flatten()
+                            .map(|&x| from_f64(x))
+                            .collect::<Result<_>>()?;
+                        Ok(Value::Matrix(Matrix::new(rows.len(), 5, data)?))
+                    }
+                }
+            }
+            "npv" => num(finance::npv(arg(0, 0.0)?, &reals(&args[1..])?)),
+            "irr" => {
+                let (values, guess) = match args {
+                    [Value::Matrix(_), rest @ ..] if rest.len() < 2 => {
+                        (reals(&args[..1])?, arg(1, 0.1)?)
+                    }
+                    _ => (reals(args)?, 0.1),
+                };
+                num(finance::irr(&values, guess).ok_or_else(no_root)?)
+            }
+            // the dates are a list of day numbers, or dates one by one
+            "xnpv" => {
+                let values = reals(&args[1..2])?;
+                let days = match &args[2..] {
+                    [Value::Date(first), ..] => args[2..]
+                        .iter()
+                        .map(|v| match v {
+                            Value::Date(d) => Ok(d.secs_since(first) as f64 / 86400.0),
+                            _ => bail!(CaculatorError::DimensionMismatch(
+                                "date".to_string(),
+                                v.dimension()
+                            )),
+                        })
+                        .collect::<Result<Vec<f64>>>()?,
+                    dates => reals(dates)?,
+                };
+                if days.len() != values.len() {
+                    bail!(CaculatorError::ShapeMismatch(
+                        format!("[{}]", values.len()),
+                        format!("[{}]", days.len())
+                    ));
+                }
+                num(finance::xnpv(arg(0, 0.0)?, &values, &days))
+            }
+            _ => bail!(CaculatorError::UnknownFunction(name.to_string())),
+        }
+    }
+
+    // the call written out, like `gcd(12, 18)`
+    fn describe_call(name: &str, args: &[Self]) -> String {
+        let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+        format!("{}({})", name, args.join(", "))
+    }
+
+    // the call has no result for these arguments, for the reason `kind`
+    fn domain_error(kind: Domain, name: &str, args: &[Self]) -> CaculatorError {
+        CaculatorError::DomainError {
+            kind,
+            op: Self::describe_call(name, args),
+            span: None,
+        }
+    }
+
+    // the integer arguments, given one by one or as a single list; a float
+    // backend has already rounded integers past 2^53, so they fail rather
+    // than give an answer about a neighbouring number
+    fn integers(name: &str, args: &[Self]) -> Result<Vec<i128>> {
+        let integer = |x: &N| match word::to_int(x) {
+            Ok(n) if !N::EXACT && n.unsigned_abs() > 1 << 53 => {
+                bail!(CaculatorError::Inexact(x.to_string()))
+            }
+            Ok(n) => Ok(n),
+            Err(_) => bail!(Self::domain_error(Domain::NotInteger, name, args)),
+        };
+        match args {
+            [Value::Matrix(m)] if m.vector => m.data.iter().map(integer).collect(),
+            _ => args.iter().map(|v| integer(v.to_number()?)).collect(),
+        }
+    }
+
+    // functions of integers; `gcd` and `lcm` take a list or any number of
+    // arguments, and arguments outside a function's domain fail
+    fn number_theory(name: &str, args: &[Self]) -> Result<Self> {
+        let domain = || Self::domain_error(Domain::OutOfRange, name, args);
+        let no_inverse = || Self::domain_error(Domain::NoInverse, name, args);
+        let ints = Self::integers(name, args)?;
+        // moduli and the numbers to factor are positive and fit in a `u64`
+        let natural = |n: i128, min: i128| match u64::try_from(n) {
+            Ok(k) if n >= min => Ok(k),
+            _ => Err(domain()),
+        };
+        let int = |x: i128| Ok(Value::Num(word::from_int(x)?));
+
+        match (name, ints.as_slice()) {
+            ("gcd", [first, rest @ ..]) if !rest.is_empty() || args.len() == 1 => {
+                int(rest.iter().fold(first.abs(), |a, &b| numtheory::gcd(a, b)))
+            }
+            ("lcm", [first, rest @ ..]) if !rest.is_empty() || args.len() == 1 => {
+                let lcm = rest
+                    .iter()
+                    .try_fold(first.abs(), |a, &b| numtheory::lcm(a, b));
+                int(lcm.ok_or_else(|| CaculatorError::Overflow {
+                    op: Self::describe_call(name, args),
+                    span: None,
+                })?)
+            }
+            ("isprime", &[n]) => int(u64::try_from(n).is_ok_and(numtheory::is_prime) as i128),
+            // one row per prime: the prime and its exponent
+            ("factor", &[n]) => {
+                let factors = numtheory::factor(natural(n, 2)?);
+                let data = factors
+                    .iter()
+                    .flat_map(|&(p, e)| [p as i128, e as i128])
+                    .map(word::from_int)
+                    .collect::<Result<_>>()?;
+                Ok(Value::Matrix(Matrix::new(factors.len(), 2, data)?))
+            }
+            ("nextprime", &[n]) => {
+                let p = numtheory::next_prime(natural(n.max(1), 1)?);
+                int(p.ok_or_else(domain)? as i128)
+            }
+            ("totient", &[n]) => int(numtheory::totient(natural(n, 1)?) as i128),
+            ("modinv", &[a, m]) => {
+                let m = natural(m, 1)?;
+                int(numtheory::mod_inv(a, m).ok_or_else(no_inverse)? as i128)
+            }
+            // a negative exponent raises the inverse
+            ("powmod", &[b, e, m]) => {
+                let m = natural(m, 1)?;
+                let b = if e < 0 {
+                    numtheory::mod_inv(b, m).ok_or_else(no_inverse)? as i128
+                } else {
+                    b
+                };
+                let e = u64::try_from(e.unsigned_abs()).map_err(|_| domain())?;
+                int(numtheory::pow_mod(b, e, m) as i128)
+            }
+            _ => {
+                let count = match name {
+                    "isprime" | "factor" | "nextprime" | "totient" => 1,
+                    "powmod" => 3,
+                    _ => 2,
+                };
+                bail!(CaculatorError::ArgumentCount(name.to_string(), count))
+            }
+        }
+    }
+
+    // exact counts; `multinomial` takes a list or any number of group sizes
+    fn combinatorics(name: &str, args: &[Self]) -> Result<Self> {
+        let domain = || Self::domain_error(Domain::OutOfRange, name, args);
+        let ints = Self::integers(name, args)?;
+        let natural = |n: i128| u64::try_from(n).map_err(|_| domain());
+        // the count with a sign, converted to the backend once at the end
+        let (negative, count) = match (name, ints.as_slice()) {
+            // `binomial` extends to negative `n` by `(-1)^k binomial(k - n - 1, k)`
+            ("binomial", &[n, k]) if n < 0 => {
+                let k = natural(k)?;
+                let n = natural((k as i128).checked_sub(n + 1).ok_or_else(domain)?)?;
+                (k % 2 == 1, combinatorics::binomial(n, k))
+            }
+            ("nCr" | "binomial", &[n, k]) => {
+                (false, combinatorics::binomial(natural(n)?, natural(k)?))
+            }
+            ("nPr", &[n, k]) => (false, combinatorics::permutations(natural(n)?, natural(k)?)),
+            ("multinomial", ks) if !ks.is_empty() => {
+                let ks = ks
+                    .iter()
+                    .map(|&k| natural(k))
+                    .collect::<Result<Vec<u64>, _>>()?;
+                (false, combinatorics::multinomial(&ks))
+            }
+            ("catalan", &[n]) => (false, combinatorics::catalan(natural(n)?)),
+            // `fib(-n)` is `(-1)^(n+1) fib(n)`
+            ("fib", &[n]) => (n < 0 && n % 2 == 0, combinatorics::fib(natural(n.abs())?)),
+            ("stirling1", &[n, k]) => (false, combinatorics::stirling1(natural(n)?, natural(k)?)),
+            ("stirling2", &[n, k]) => (false, combinatorics::stirling2(natural(n)?, natural(k)?)),
+            ("derangements", &[n]) => (false, combinatorics::derangements(natural(n)?)),
+            _ => {
+                let count = match name {
+                    "catalan" | "fib" | "derangements" | "multinomial" => 1,
+                    _ => 2,
+                };
+                bail!(CaculatorError::ArgumentCount(name.to_string(), count))
+            }
+        };
+        let overflow = || CaculatorError::Overflow {
+            op: Self::describe_call(name, args),
+            span: None,
+        };
+        let count = count.ok_or_else(overflow)?;
+        // counts beyond `i128` only fit the floating point backends, rounded once
+        let n = match count.to_u128().and_then(|x| i128::try_from(x).ok()) {
+            Some(x) => word::from_int(x)?,
+            None => N::parse(&count.to_string())
+                .ok()
+                .filter(|x| x.to_f64().is_some_and(f64::is_finite))
+                .ok_or_else(overflow)?,
+        };
+        Ok(Value::Num(if negative { n.neg()? } else { n }))
+    }
+
+    /// Writes the value with the given notation; dates keep their own format.
+    pub fn format(&self, format: &Format) -> String {
+        match self {
+            Value::Num(n) => n.format(format),
+            Value::Quantity(q) => q.format(format),
+            Value::Date(d) => d.to_string(),
+            Value::Interval(x) => x.format(format),
+            Value::Matrix(m) => m.format(format),
+            Value::Expr(e) => e.format(format),
+        }
+    }
+
+    /// False if the value holds an infinity or `NaN`.
+    pub fn is_finite(&self) -> bool {
+        match self {
+            Value::Num(n) => n.is_finite(),
+            Value::Quantity(q) => q.value.is_finite(),
+            Value::Date(_) => true,
+            Value::Interval(x) => x.lo.is_finite() && x.hi.is_finite(),
+            Value::Matrix(m) => m.data.iter().all(N::is_finite),
+            Value::Expr(_) => true,
+        }
+    }
+
+    pub fn is_nan(&self) -> bool {
+        match self {
+            Value::Num(n) => n.is_nan(),
+            Value::Quantity(q) => q.value.is_nan(),
+            Value::Date(_) => false,
+            Value::Interval(x) => x.lo.is_nan() || x.hi.is_nan(),
+            Value::Matrix(m) => m.data.iter().any(N::is_nan),
+            Value::Expr(_) => false,
+        }
+    }
+
+    /// Writes a plain number in base `radix`, e.g. `FF` for 255 in base 16.
+    pub fn radix(&self, radix: u32) -> Result<String> {
+        self.to_number()?.radix(radix)
+    }
+
+    fn mismatch(&self, rhs: &Self) -> CaculatorError {
+        CaculatorError::DimensionMismatch(self.dimension(), rhs.dimension())
+    }
+
+    fn dimension(&self) -> String {
+        match self {
+            Value::Num(_) => "1".to_string(),
+            Value::Quantity(q) => q.dim.to_string(),
+            Value::Date(_) => "date".to_string(),
+            Value::Interval(_) => "1".to_string(),
+            Value::Matrix(m) => m.shape(),
+            Value::Expr(e) => e.to_string(),
+        }
+    }
+}
+
+// a quantity whose units cancel out is a plain number
+impl<N> From<Quantity<N>> for Value<N> {
+    fn from(q: Quantity<N>) -> Self {
+        if q.dim.is_none() {
+            return Value::Num(q.value);
+        }
+        Value::Quantity(q)
+    }
+}
+
+impl<N: Number> fmt::Display for Value<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Num(n) => write!(f, "{}", n),
+            Value::Quantity(q) => write!(f, "{}", q),
+            Value::Date(d) => write!(f, "{}", d),
+            Value::Interval(x) => write!(f, "{}", x),
+            Value::Matrix(m) => write!(f, "{}", m),
+            Value::Expr(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+// a real number of the backend, through its decimal digits
+pub(crate) fn from_f64<N: Number>(x: f64) -> Result<N> {
+    let n = N::parse(&x.abs().to_string())?;
+    if x < 0.0 {
+        return n.neg();
+    }
+    Ok(n)
+}
+
+// the length of a duration in seconds, for moving dates
+fn duration_secs<N: Number>(q: &Quantity<N>) -> Result<f64> {
+    match (q.dim, q.value.to_f64()) {
+        (Dimension::TIME, Some(secs)) => Ok(secs),
+        _ => bail!(CaculatorError::DimensionMismatch(
+            "date".to_string(),
+            q.dim.to_string()
+        )),
+    }
+}
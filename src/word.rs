@@ -0,0 +1,332 @@
+use anyhow::{bail, Result};
+use std::fmt;
+
+use crate::errors::CaculatorError;
+use crate::format;
+use crate::number::Number;
+
+/// A fixed-width integer type such as `u8` or `i32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntType {
+    pub bits: u32,
+    pub signed: bool,
+}
+
+impl IntType {
+    pub const ALL: [IntType; 8] = [
+        IntType::new(8, true),
+        IntType::new(16, true),
+        IntType::new(32, true),
+        IntType::new(64, true),
+        IntType::new(8, false),
+        IntType::new(16, false),
+        IntType::new(32, false),
+        IntType::new(64, false),
+    ];
+
+    pub const fn new(bits: u32, signed: bool) -> Self {
+        Self { bits, signed }
+    }
+
+    /// Parses a type name: `i8`, `i16`, `i32`, `i64` or their `u` versions.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ty| ty.to_string() == name)
+    }
+
+    pub fn min(&self) -> i128 {
+        if self.signed {
+            -(1 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    pub fn max(&self) -> i128 {
+        if self.signed {
+            (1 << (self.bits - 1)) - 1
+        } else {
+            (1 << self.bits) - 1
+        }
+    }
+
+    fn mask(&self) -> u128 {
+        (1 << self.bits) - 1
+    }
+}
+
+impl Default for IntType {
+    fn default() -> Self {
+        Self::new(64, true)
+    }
+}
+
+impl fmt::Display for IntType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", if self.signed { "i" } else { "u" }, self.bits)
+    }
+}
+
+/// What happens to a result that does not fit the integer type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowMode {
+    /// Keeps the low bits, so `127 + 1` is `-128` in `i8`.
+    #[default]
+    Wrapping,
+    /// Clamps to the nearest representable value.
+    Saturating,
+    /// Fails with `Overflow`.
+    Checked,
+}
+
+/// Programmer mode: arithmetic on integers of one type with one overflow mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WordMode {
+    pub ty: IntType,
+    pub overflow: OverflowMode,
+}
+
+/// True for the functions working on bit patterns, like `xor(a, b)`.
+pub fn is_bitwise(name: &str) -> bool {
+    matches!(name, "and" | "or" | "xor" | "not" | "shl" | "shr")
+}
+
+/// The integer a backend number holds, failing for fractions.
+pub fn to_int<N: Number>(x: &N) -> Result<i128> {
+    // exact backends write integers beyond the precision of `f64` in full
+    if let Ok(n) = x.to_string().parse::<i128>() {
+        return Ok(n);
+    }
+    match x.to_f64() {
+        Some(f) if f.fract() == 0.0 && f.abs() < 2f64.powi(127) => Ok(f as i128),
+        _ => bail!(CaculatorError::NotInteger(x.to_string())),
+    }
+}
+
+pub fn from_int<N: Number>(x: i128) -> Result<N> {
+    let n = N::parse(&x.unsigned_abs().to_string())?;
+    if x < 0 {
+        return n.neg();
+    }
+    Ok(n)
+}
+
+impl WordMode {
+    /// Brings an integer into range by the overflow mode.
+    pub fn fit(&self, x: i128) -> Result<i128> {
+        self.fit_or(Some(x), x, x < 0, || x.to_string())
+    }
+
+    /// The number `x` of the backend as an integer of this type.
+    pub fn wrap<N: Number>(&self, x: &N) -> Result<N> {
+        from_int(self.fit(to_int(x)?)?)
+    }
+
+    // `exact` is `None` if the result overflowed even `i128`, then `wrapped`
+    // has its low bits and `negative` its sign
+    fn fit_or(
+        &self,
+        exact: Option<i128>,
+        wrapped: i128,
+        negative: bool,
+        op: impl Fn() -> String,
+    ) -> Result<i128> {
+        if let Some(x) = exact.filter(|x| (self.ty.min()..=self.ty.max()).contains(x)) {
+            return Ok(x);
+        }
+        match self.overflow {
+            OverflowMode::Wrapping => Ok(self.from_bits(self.bits(wrapped))),
+            OverflowMode::Saturating if negative => Ok(self.ty.min()),
+            OverflowMode::Saturating => Ok(self.ty.max()),
+            OverflowMode::Checked => bail!(CaculatorError::Overflow {
+                op: op(),
+                span: None
+            }),
+        }
+    }
+
+    /// The two's complement bit pattern of `x`.
+    pub fn bits(&self, x: i128) -> u64 {
+        (x as u128 & self.ty.mask()) as u64
+    }
+
+    /// The integer with the bit pattern, negative if a signed type's top bit is set.
+    pub fn from_bits(&self, bits: u64) -> i128 {
+        let x = bits as u128 & self.ty.mask();
+        if self.ty.signed && x >> (self.ty.bits - 1) == 1 {
+            return x as i128 - (1 << self.ty.bits);
+        }
+        x as i128
+    }
+
+    /// Applies an arithmetic operator, `None` for operators integers lack.
+    pub fn apply<N: Number>(&self, op: char, a: &N, b: &N) -> Result<Option<N>> {
+        let (a, b) = (self.fit(to_int(a)?)?, self.fit(to_int(b)?)?);
+        let describe = || format!("{} {} {}", a, op, b);
+        let x = match op {
+            '+' => self.fit_or(a.checked_add(b), a.wrapping_add(b), a < 0, describe)?,
+            '-' => self.fit_or(a.checked_sub(b), a.wrapping_sub(b), a < b, describe)?,
+            '*' => {
+                let negative = (a < 0) != (b < 0);
+                self.fit_or(a.checked_mul(b), a.wrapping_mul(b), negative, describe)?
+            }
+            // integer division truncates toward zero
+            '/' => {
+                if b == 0 {
+                    bail!(CaculatorError::DivideByZero);
+                }
+                self.fit(a / b)?
+            }
+            // and the remainder takes the sign of `a`
+            '%' => {
+                if b == 0 {
+                    bail!(CaculatorError::DivideByZero);
+                }
+                self.fit(a % b)?
+            }
+            '^' => {
+                let Ok(exp) = u32::try_from(b) else {
+                    bail!(CaculatorError::InvalidExpression(describe()));
+                };
+                let negative = a < 0 && exp % 2 == 1;
+                let wrapped = self.wrapping_pow(a, exp);
+                self.fit_or(a.checked_pow(exp), wrapped, negative, describe)?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(from_int(x)?))
+    }
+
+    // `a^exp` modulo the width, by squaring
+    fn wrapping_pow(&self, mut a: i128, mut exp: u32) -> i128 {
+        let mut acc: i128 = 1;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                acc = self.from_bits(self.bits(acc.wrapping_mul(a)));
+            }
+            a = self.from_bits(self.bits(a.wrapping_mul(a)));
+            exp >>= 1;
+        }
+        acc
+    }
+
+    pub fn neg<N: Number>(&self, a: &N) -> Result<N> {
+        let a = self.fit(to_int(a)?)?;
+        from_int(self.fit_or(Some(-a), -a, a > 0, || format!("-{}", a))?)
+    }
+
+    /// Calls one of the bitwise functions on the two's complement patterns.
+    pub fn call<N: Number>(&self, name: &str, args: &[N]) -> Result<N> {
+        let args = args
+            .iter()
+            .map(|x| self.fit(to_int(x)?))
+            .collect::<Result<Vec<i128>>>()?;
+        let shift = |n: i128| match u32::try_from(n) {
+            Ok(n) if n < self.ty.bits => Ok(n),
+            _ => {
+                let args: Vec<String> = args.iter().map(i128::to_string).collect();
+                bail!(CaculatorError::InvalidExpression(format!(
+                    "{}({})",
+                    name,
+                    args.join(", ")
+                )))
+            }
+        };
+        let x = match (name, args.as_slice()) {
+            ("and", &[a, b]) => self.from_bits(self.bits(a) & self.bits(b)),
+            ("or", &[a, b]) => self.from_bits(self.bits(a) | self.bits(b)),
+            ("xor", &[a, b]) => self.from_bits(self.bits(a) ^ self.bits(b)),
+            ("not", &[a]) => self.from_bits(!self.bits(a)),
+            ("shl", &[a, n]) => self.from_bits(self.bits(a) << shift(n)?),
+            // arithmetic for signed types, logical for unsigned ones
+            ("shr", &[a, n]) => a >> shift(n)?,
+            ("not", _) => bail!(CaculatorError::ArgumentCount(name.to_string(), 1)),
+            _ => bail!(CaculatorError::ArgumentCount(name.to_string(), 2)),
+        };
+        from_int(x)
+    }
+
+    /// Writes the two's complement pattern of `x` in base `radix`, so `-1`
+    /// is `FF` in `i8`.
+    pub fn radix(&self, x: i128, radix: u32) -> Result<String> {
+        format::check_radix(radix)?;
+        let mut bits = self.bits(x);
+        let mut digits = Vec::new();
+        loop {
+            let d = std::char::from_digit((bits % radix as u64) as u32, radix).unwrap_or('?');
+            digits.push(d.to_ascii_uppercase());
+            bits /= radix as u64;
+            if bits == 0 {
+                break;
+            }
+        }
+        Ok(digits.into_iter().rev().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rational::Rational;
+
+    fn mode(name: &str, overflow: OverflowMode) -> WordMode {
+        WordMode {
+            ty: IntType::from_name(name).unwrap(),
+            overflow,
+        }
+    }
+
+    #[test]
+    fn test_types() {
+        let i8 = IntType::from_name("i8").unwrap();
+        assert_eq!((i8.min(), i8.max()), (-128, 127));
+        let u64 = IntType::from_name("u64").unwrap();
+        assert_eq!((u64.min(), u64.max()), (0, u64::MAX as i128));
+        assert_eq!(IntType::default().to_string(), "i64");
+        assert_eq!(IntType::from_name("i128"), None);
+    }
+
+    #[test]
+    fn test_overflow() -> anyhow::Result<()> {
+        let t_cases = vec![
+            ("i8", OverflowMode::Wrapping, 128, Some(-128)),
+            ("i8", OverflowMode::Saturating, 200, Some(127)),
+            ("i8", OverflowMode::Saturating, -200, Some(-128)),
+            ("u8", OverflowMode::Wrapping, -1, Some(255)),
+            ("u8", OverflowMode::Saturating, -1, Some(0)),
+            ("u16", OverflowMode::Checked, 65535, Some(65535)),
+            ("u16", OverflowMode::Checked, 65536, None),
+        ];
+        for (ty, overflow, x, expected) in t_cases {
+            let r = mode(ty, overflow).fit(x);
+            assert_eq!(r.ok(), expected, "{} {:?} {}", ty, overflow, x);
+        }
+
+        let u64 = mode("u64", OverflowMode::Wrapping);
+        let max = Rational::from_int(u64::MAX as i128);
+        let square = u64.apply('*', &max, &max)?.unwrap();
+        assert_eq!(square, Rational::from_int(1));
+        let u64 = mode("u64", OverflowMode::Saturating);
+        assert_eq!(u64.apply('*', &max, &max)?, Some(max));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bits() -> anyhow::Result<()> {
+        let i8 = mode("i8", OverflowMode::Wrapping);
+        assert_eq!(i8.bits(-1), 0xFF);
+        assert_eq!(i8.from_bits(0x80), -128);
+        assert_eq!(i8.radix(-2, 16)?, "FE");
+        assert_eq!(i8.radix(5, 2)?, "101");
+        let call = |name: &str, args: &[i128]| {
+            let args: Vec<Rational> = args.iter().map(|&x| Rational::from_int(x)).collect();
+            i8.call(name, &args)
+        };
+        assert_eq!(call("not", &[0])?, Rational::from_int(-1));
+        assert_eq!(call("shl", &[1, 7])?, Rational::from_int(-128));
+        assert_eq!(call("shr", &[-128, 7])?, Rational::from_int(-1));
+        assert_eq!(call("xor", &[0b1100, 0b1010])?, Rational::from_int(0b0110));
+        let err = call("shl", &[1, 8]).unwrap_err();
+        assert_eq!(err.to_string(), "invalid expression: shl(1, 8)");
+        Ok(())
+    }
+}